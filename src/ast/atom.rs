@@ -1,6 +1,9 @@
+use crate::bigint::BigInt;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Atom {
     Integer(i64),
+    BigInt(BigInt),
     Float(f64),
     String(String),
     Bool(bool),
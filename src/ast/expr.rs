@@ -1,13 +1,33 @@
 use crate::lexer::TokenType;
 
-use super::atom::Atom;
+use super::{atom::Atom, pattern::Pattern};
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Expr {
     // left, operator, right
     Binary(Box<Expr>, TokenType, Box<Expr>),
+    // target, `=` or a compound operator such as `+=`, value
     Assignment(Box<Expr>, TokenType, Box<Expr>),
     Identifier(String),
     CallExpr(String, Vec<Expr>),
     Literal(Atom),
+    // [a, b, ...]
+    List(Vec<Expr>),
+    // { key: value, ... }
+    Map(Vec<(Expr, Expr)>),
+    // target, index
+    Index(Box<Expr>, Box<Expr>),
+    // Name { field: value, ... }
+    StructLiteral(String, Vec<(String, Expr)>),
+    // target.field
+    Member(Box<Expr>, String),
+    // target.method(args)
+    MethodCall(Box<Expr>, String, Vec<Expr>),
+    // Enum::Variant(args)
+    Variant(String, String, Vec<Expr>),
+    // subject, arms
+    Match(Box<Expr>, Vec<(Pattern, Expr)>),
+    // expr?, unwraps `Some`/`Ok` or returns the `None`/`Err` from the function
+    Try(Box<Expr>),
 }
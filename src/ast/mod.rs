@@ -1,4 +1,6 @@
 pub mod atom;
 pub mod expr;
+pub mod pattern;
 pub mod program;
 pub mod stmt;
+pub mod types;
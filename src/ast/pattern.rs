@@ -0,0 +1,26 @@
+use super::atom::Atom;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    // _
+    Wildcard,
+    // 1, 'a', true, ...
+    Literal(Atom),
+    // name, binds the matched value
+    Binding(String),
+    // enum, variant, fields
+    Variant(String, String, Vec<Pattern>),
+}
+
+impl Pattern {
+    /// Names bound by the pattern, in the order they appear.
+    pub fn bindings(&self) -> Vec<&String> {
+        match self {
+            Pattern::Binding(name) => vec![name],
+            Pattern::Variant(_, _, patterns) => {
+                patterns.iter().flat_map(Pattern::bindings).collect()
+            }
+            Pattern::Wildcard | Pattern::Literal(_) => vec![],
+        }
+    }
+}
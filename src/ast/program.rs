@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Write};
 
 use super::stmt::Stmt;
 
@@ -6,3 +6,52 @@ use super::stmt::Stmt;
 pub struct Program {
     pub statements: Vec<Stmt>,
 }
+
+impl Program {
+    /// Text dump of the syntax tree, one numbered entry per top level
+    /// statement, stable enough to diff the output of the optimizer or to use
+    /// in snapshot tests.
+    pub fn dump(&self) -> String {
+        let mut text = String::new();
+        for (index, stmt) in self.statements.iter().enumerate() {
+            writeln!(text, "{index:04} {stmt:#?}").expect("Failed to write dump");
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{optimizer::Optimizer, parser::Parser};
+
+    #[test]
+    fn dump() {
+        let program = Parser::new("const day: int = 60 * 60 * 24;\nprint day;")
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            Optimizer::new().optimize(program).dump(),
+            "\
+0000 VarDeclaration(
+    \"day\",
+    Int,
+    true,
+    Literal(
+        Integer(
+            86400,
+        ),
+    ),
+)
+0001 Print(
+    Literal(
+        Integer(
+            86400,
+        ),
+    ),
+)
+"
+        );
+    }
+}
@@ -1,17 +1,27 @@
-use crate::lexer::TokenType;
-
-use super::expr::Expr;
+use super::{expr::Expr, types::Type};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Stmt {
     // name, type, is_const, value
-    VarDeclaration(String, TokenType, bool, Expr),
+    VarDeclaration(String, Type, bool, Expr),
     // name, parameters, body
     FuncDeclaration(String, Vec<String>, Box<Stmt>),
+    // name, fields
+    StructDeclaration(String, Vec<(String, Type)>),
+    // name, variants with their field types
+    EnumDeclaration(String, Vec<(String, Vec<Type>)>),
+    // type name, methods
+    ImplDeclaration(String, Vec<Stmt>),
     // { ... }
     Block(Vec<Stmt>),
     // return ...
     Return(Expr),
+    // condition, then, else
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    // throw ...
+    Throw(Expr),
+    // body, catch variable and block, finally block
+    Try(Box<Stmt>, Option<(String, Box<Stmt>)>, Option<Box<Stmt>>),
     Print(Expr),
     Expression(Expr),
 }
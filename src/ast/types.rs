@@ -0,0 +1,16 @@
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    Int,
+    BigInt,
+    Float,
+    String,
+    Bool,
+    // list<element>
+    List(Box<Type>),
+    // map<key, value>
+    Map(Box<Type>, Box<Type>),
+    // Name of a declared struct or enum
+    Named(String),
+    // Accepts every value, only used by the fields of the built-in enums
+    Any,
+}
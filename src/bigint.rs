@@ -0,0 +1,371 @@
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    ops::{Add, Mul, Neg, Sub},
+    str::FromStr,
+};
+
+/// Arbitrary-precision signed integer backing the `bigint` type.
+///
+/// The magnitude is stored as base 2^32 limbs, least significant first, without
+/// trailing zero limbs so every value has a single representation (zero has no
+/// limbs and is never negative).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    limbs: Vec<u32>,
+}
+
+impl BigInt {
+    fn new(negative: bool, mut limbs: Vec<u32>) -> Self {
+        while limbs.last() == Some(&0) {
+            limbs.pop();
+        }
+        let negative = negative && !limbs.is_empty();
+
+        BigInt { negative, limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Number of bits in the magnitude, zero for zero.
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() as u64 * 32 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    /// Quotient truncated toward zero like `i64` division, `None` for a zero
+    /// divisor.
+    pub fn checked_div(&self, other: &BigInt) -> Option<BigInt> {
+        if other.is_zero() {
+            return None;
+        }
+
+        let (quotient, _) = div_rem_magnitude(&self.limbs, &other.limbs);
+        Some(BigInt::new(self.negative != other.negative, quotient))
+    }
+
+    /// Remainder with the sign of the dividend like `i64::rem`, `None` for a
+    /// zero divisor.
+    pub fn checked_rem(&self, other: &BigInt) -> Option<BigInt> {
+        if other.is_zero() {
+            return None;
+        }
+
+        let (_, remainder) = div_rem_magnitude(&self.limbs, &other.limbs);
+        Some(BigInt::new(self.negative, remainder))
+    }
+
+    pub fn pow(&self, mut exponent: u32) -> BigInt {
+        // Exponentiation by squaring
+        let mut result = BigInt::from(1);
+        let mut base = self.clone();
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base.clone();
+            }
+            exponent >>= 1;
+            if exponent > 0 {
+                base = base.clone() * base;
+            }
+        }
+
+        result
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(val: i64) -> Self {
+        let magnitude = val.unsigned_abs();
+        BigInt::new(val < 0, vec![magnitude as u32, (magnitude >> 32) as u32])
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseBigIntError;
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, s),
+        };
+        if digits.is_empty() {
+            return Err(ParseBigIntError);
+        }
+
+        let mut limbs = vec![];
+        for digit in digits.chars() {
+            let digit = digit.to_digit(10).ok_or(ParseBigIntError)?;
+            limbs = mul_add_small(&limbs, 10, digit);
+        }
+
+        Ok(BigInt::new(negative, limbs))
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Split into base 10^9 chunks, least significant first
+        let mut chunks = vec![];
+        let mut limbs = self.limbs.clone();
+        while !limbs.is_empty() {
+            let (quotient, remainder) = div_rem_small(&limbs, 1_000_000_000);
+            chunks.push(remainder);
+            limbs = quotient;
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        if let Some(first) = chunks.next() {
+            write!(f, "{first}")?;
+        }
+        for chunk in chunks {
+            write!(f, "{chunk:09}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_magnitude(&self.limbs, &other.limbs),
+            (true, true) => cmp_magnitude(&other.limbs, &self.limbs),
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> Self::Output {
+        BigInt::new(!self.negative, self.limbs)
+    }
+}
+
+impl Add for BigInt {
+    type Output = BigInt;
+
+    fn add(self, other: Self) -> Self::Output {
+        if self.negative == other.negative {
+            return BigInt::new(self.negative, add_magnitude(&self.limbs, &other.limbs));
+        }
+
+        // Opposite signs, the result takes the sign of the larger magnitude
+        match cmp_magnitude(&self.limbs, &other.limbs) {
+            Ordering::Less => BigInt::new(other.negative, sub_magnitude(&other.limbs, &self.limbs)),
+            _ => BigInt::new(self.negative, sub_magnitude(&self.limbs, &other.limbs)),
+        }
+    }
+}
+
+impl Sub for BigInt {
+    type Output = BigInt;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self + -other
+    }
+}
+
+impl Mul for BigInt {
+    type Output = BigInt;
+
+    fn mul(self, other: Self) -> Self::Output {
+        let mut limbs = vec![0u32; self.limbs.len() + other.limbs.len()];
+        for (i, left) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+            for (j, right) in other.limbs.iter().enumerate() {
+                let product = *left as u64 * *right as u64 + limbs[i + j] as u64 + carry;
+                limbs[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+
+        BigInt::new(self.negative != other.negative, limbs)
+    }
+}
+
+fn cmp_magnitude(left: &[u32], right: &[u32]) -> Ordering {
+    left.len()
+        .cmp(&right.len())
+        .then_with(|| left.iter().rev().cmp(right.iter().rev()))
+}
+
+fn add_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(left.len().max(right.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..left.len().max(right.len()) {
+        let sum = *left.get(i).unwrap_or(&0) as u64 + *right.get(i).unwrap_or(&0) as u64 + carry;
+        limbs.push(sum as u32);
+        carry = sum >> 32;
+    }
+    limbs.push(carry as u32);
+
+    limbs
+}
+
+/// Subtract magnitudes, `left` must not be smaller than `right`.
+fn sub_magnitude(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut limbs = Vec::with_capacity(left.len());
+    let mut borrow = 0i64;
+    for (i, limb) in left.iter().enumerate() {
+        let mut difference = *limb as i64 - *right.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if difference < 0 {
+            difference += 1 << 32;
+            borrow = 1;
+        }
+        limbs.push(difference as u32);
+    }
+
+    while limbs.last() == Some(&0) {
+        limbs.pop();
+    }
+    limbs
+}
+
+fn mul_add_small(limbs: &[u32], factor: u32, addend: u32) -> Vec<u32> {
+    let mut result = Vec::with_capacity(limbs.len() + 1);
+    let mut carry = addend as u64;
+    for limb in limbs {
+        let product = *limb as u64 * factor as u64 + carry;
+        result.push(product as u32);
+        carry = product >> 32;
+    }
+    if carry > 0 {
+        result.push(carry as u32);
+    }
+
+    result
+}
+
+fn div_rem_small(limbs: &[u32], divisor: u32) -> (Vec<u32>, u32) {
+    let mut quotient = vec![0u32; limbs.len()];
+    let mut remainder = 0u64;
+    for (i, limb) in limbs.iter().enumerate().rev() {
+        let current = (remainder << 32) | *limb as u64;
+        quotient[i] = (current / divisor as u64) as u32;
+        remainder = current % divisor as u64;
+    }
+
+    while quotient.last() == Some(&0) {
+        quotient.pop();
+    }
+    (quotient, remainder as u32)
+}
+
+/// Long division of magnitudes, one bit of the dividend at a time. `divisor`
+/// must not be zero.
+fn div_rem_magnitude(dividend: &[u32], divisor: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [divisor] = divisor {
+        let (quotient, remainder) = div_rem_small(dividend, *divisor);
+        return (quotient, vec![remainder]);
+    }
+
+    let mut quotient = vec![0u32; dividend.len()];
+    let mut remainder: Vec<u32> = vec![];
+    for bit in (0..dividend.len() * 32).rev() {
+        // remainder = remainder * 2 + current bit
+        let mut carry = (dividend[bit / 32] >> (bit % 32)) & 1;
+        for limb in remainder.iter_mut() {
+            let next_carry = *limb >> 31;
+            *limb = (*limb << 1) | carry;
+            carry = next_carry;
+        }
+        if carry > 0 {
+            remainder.push(carry);
+        }
+
+        if cmp_magnitude(&remainder, divisor) != Ordering::Less {
+            remainder = sub_magnitude(&remainder, divisor);
+            quotient[bit / 32] |= 1 << (bit % 32);
+        }
+    }
+
+    (quotient, remainder)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn big(s: &str) -> BigInt {
+        s.parse().expect("Failed to parse bigint")
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for s in [
+            "0",
+            "1",
+            "-1",
+            "4294967296",
+            "123456789012345678901234",
+            "-1000000000",
+        ] {
+            assert_eq!(big(s).to_string(), s);
+        }
+        assert_eq!(big("-0").to_string(), "0");
+        assert_eq!(BigInt::from(i64::MIN).to_string(), i64::MIN.to_string());
+        assert_eq!("12a".parse::<BigInt>(), Err(ParseBigIntError));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = big("123456789012345678901234");
+        let b = big("-987654321098765432");
+
+        assert_eq!(
+            (a.clone() + b.clone()).to_string(),
+            "123455801358024580135802"
+        );
+        assert_eq!(
+            (a.clone() - b.clone()).to_string(),
+            "123457776666666777666666"
+        );
+        assert_eq!(
+            (a.clone() * b.clone()).to_string(),
+            "-121932631137021795212619466642127661343088"
+        );
+        assert_eq!(
+            a.checked_div(&b).map(|q| q.to_string()),
+            Some("-124999".to_string())
+        );
+        assert_eq!(
+            a.checked_rem(&b).map(|r| r.to_string()),
+            Some("986529321098666666".to_string())
+        );
+        assert_eq!(a.checked_div(&big("0")), None);
+    }
+
+    #[test]
+    fn ordering() {
+        assert!(big("-5") < big("-4"));
+        assert!(big("-1") < big("0"));
+        assert!(big("4294967296") > big("4294967295"));
+        assert_eq!(big("10") - big("10"), big("0"));
+    }
+}
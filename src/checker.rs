@@ -0,0 +1,418 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    ast::{atom::Atom, expr::Expr, pattern::Pattern, program::Program, stmt::Stmt},
+    stack::ensure_sufficient_stack,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CheckError {
+    UndefinedEnum(String),
+    UndefinedVariant(String),
+    // Patterns not covered by the arms of a `match`, e.g. `Shape::Rect(..)`
+    NonExhaustiveMatch(Vec<String>),
+}
+
+impl Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckError::UndefinedEnum(name) => write!(f, "undefined enum `{name}`"),
+            CheckError::UndefinedVariant(name) => write!(f, "undefined variant `{name}`"),
+            CheckError::NonExhaustiveMatch(missing) => {
+                write!(f, "non exhaustive match, missing {}", missing.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for CheckError {}
+
+/// Static checks run on the whole program before evaluation.
+///
+/// Every `match` must be exhaustive: it needs a catch-all arm (`_` or a
+/// binding), or arms covering every variant of the matched enum, or both
+/// `true` and `false`. The check is conservative, a variant with several
+/// fields is only covered by an arm whose field patterns are all catch-alls.
+pub struct Checker {
+    // enum name -> variant name, number of fields
+    enums: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        let enums = HashMap::from([
+            (
+                "Option".to_string(),
+                vec![("Some".to_string(), 1), ("None".to_string(), 0)],
+            ),
+            (
+                "Result".to_string(),
+                vec![("Ok".to_string(), 1), ("Err".to_string(), 1)],
+            ),
+        ]);
+
+        Checker { enums }
+    }
+
+    pub fn check(&mut self, program: &Program) -> Result<(), CheckError> {
+        // Enums can be used by functions declared before them
+        for stmt in &program.statements {
+            self.collect_enums(stmt);
+        }
+
+        for stmt in &program.statements {
+            self.check_stmt(stmt)?;
+        }
+
+        Ok(())
+    }
+
+    fn collect_enums(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::EnumDeclaration(name, variants) => {
+                let variants = variants
+                    .iter()
+                    .map(|(variant, fields)| (variant.clone(), fields.len()))
+                    .collect();
+                self.enums.insert(name.clone(), variants);
+            }
+            Stmt::Block(stmts) | Stmt::ImplDeclaration(_, stmts) => {
+                for stmt in stmts {
+                    self.collect_enums(stmt);
+                }
+            }
+            Stmt::FuncDeclaration(_, _, body) => self.collect_enums(body),
+            Stmt::Try(body, catch, finally) => {
+                self.collect_enums(body);
+                if let Some((_, block)) = catch {
+                    self.collect_enums(block);
+                }
+                if let Some(block) = finally {
+                    self.collect_enums(block);
+                }
+            }
+            Stmt::If(_, then_branch, else_branch) => {
+                self.collect_enums(then_branch);
+                if let Some(branch) = else_branch {
+                    self.collect_enums(branch);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_stmt(&self, stmt: &Stmt) -> Result<(), CheckError> {
+        ensure_sufficient_stack(|| match stmt {
+            Stmt::VarDeclaration(_, _, _, expr)
+            | Stmt::Return(expr)
+            | Stmt::Print(expr)
+            | Stmt::Throw(expr)
+            | Stmt::Expression(expr) => self.check_expr(expr),
+            Stmt::FuncDeclaration(_, _, body) => self.check_stmt(body),
+            Stmt::Block(stmts) | Stmt::ImplDeclaration(_, stmts) => {
+                stmts.iter().try_for_each(|stmt| self.check_stmt(stmt))
+            }
+            Stmt::If(condition, then_branch, else_branch) => {
+                self.check_expr(condition)?;
+                self.check_stmt(then_branch)?;
+                match else_branch {
+                    Some(branch) => self.check_stmt(branch),
+                    None => Ok(()),
+                }
+            }
+            Stmt::Try(body, catch, finally) => {
+                self.check_stmt(body)?;
+                if let Some((_, block)) = catch {
+                    self.check_stmt(block)?;
+                }
+                match finally {
+                    Some(block) => self.check_stmt(block),
+                    None => Ok(()),
+                }
+            }
+            Stmt::StructDeclaration(..) | Stmt::EnumDeclaration(..) => Ok(()),
+        })
+    }
+
+    fn check_expr(&self, expr: &Expr) -> Result<(), CheckError> {
+        ensure_sufficient_stack(|| match expr {
+            Expr::Binary(lhs, _, rhs) | Expr::Assignment(lhs, _, rhs) | Expr::Index(lhs, rhs) => {
+                self.check_expr(lhs)?;
+                self.check_expr(rhs)
+            }
+            Expr::CallExpr(_, args) | Expr::List(args) | Expr::Variant(_, _, args) => {
+                args.iter().try_for_each(|arg| self.check_expr(arg))
+            }
+            Expr::Map(entries) => entries.iter().try_for_each(|(key, value)| {
+                self.check_expr(key)?;
+                self.check_expr(value)
+            }),
+            Expr::StructLiteral(_, fields) => fields
+                .iter()
+                .try_for_each(|(_, value)| self.check_expr(value)),
+            Expr::Member(target, _) | Expr::Try(target) => self.check_expr(target),
+            Expr::MethodCall(target, _, args) => {
+                self.check_expr(target)?;
+                args.iter().try_for_each(|arg| self.check_expr(arg))
+            }
+            Expr::Match(subject, arms) => {
+                self.check_expr(subject)?;
+                for (_, expr) in arms {
+                    self.check_expr(expr)?;
+                }
+
+                let patterns: Vec<&Pattern> = arms.iter().map(|(pattern, _)| pattern).collect();
+                let missing = self.missing_patterns(&patterns)?;
+                if !missing.is_empty() {
+                    return Err(CheckError::NonExhaustiveMatch(missing));
+                }
+
+                Ok(())
+            }
+            Expr::Identifier(_) | Expr::Literal(_) => Ok(()),
+        })
+    }
+
+    /// Patterns of values matched by none of the given patterns, empty when
+    /// the patterns are exhaustive.
+    fn missing_patterns(&self, patterns: &[&Pattern]) -> Result<Vec<String>, CheckError> {
+        if patterns.iter().any(|pattern| Self::is_catch_all(pattern)) {
+            return Ok(vec![]);
+        }
+
+        let enum_name = patterns.iter().find_map(|pattern| match pattern {
+            Pattern::Variant(name, ..) => Some(name),
+            _ => None,
+        });
+        if let Some(enum_name) = enum_name {
+            return self.missing_variants(enum_name, patterns);
+        }
+
+        let has_bool = |expected: bool| {
+            patterns
+                .iter()
+                .any(|pattern| **pattern == Pattern::Literal(Atom::Bool(expected)))
+        };
+        if patterns
+            .iter()
+            .all(|pattern| matches!(pattern, Pattern::Literal(Atom::Bool(_))))
+            && !patterns.is_empty()
+        {
+            return Ok([true, false]
+                .into_iter()
+                .filter(|val| !has_bool(*val))
+                .map(|val| val.to_string())
+                .collect());
+        }
+
+        Ok(vec!["_".to_string()])
+    }
+
+    fn missing_variants(
+        &self,
+        enum_name: &str,
+        patterns: &[&Pattern],
+    ) -> Result<Vec<String>, CheckError> {
+        let variants = self
+            .enums
+            .get(enum_name)
+            .ok_or_else(|| CheckError::UndefinedEnum(enum_name.to_string()))?;
+
+        for pattern in patterns {
+            if let Pattern::Variant(name, variant, _) = pattern {
+                if name != enum_name || !variants.iter().any(|(declared, _)| declared == variant) {
+                    return Err(CheckError::UndefinedVariant(format!("{name}::{variant}")));
+                }
+            }
+        }
+
+        let mut missing = vec![];
+        for (variant, arity) in variants {
+            let fields: Vec<&Vec<Pattern>> = patterns
+                .iter()
+                .filter_map(|pattern| match pattern {
+                    Pattern::Variant(_, name, fields) if name == variant => Some(fields),
+                    _ => None,
+                })
+                .collect();
+
+            let full_name = format!("{enum_name}::{variant}");
+            if *arity == 0 {
+                if fields.is_empty() {
+                    missing.push(full_name);
+                }
+                continue;
+            }
+
+            if fields
+                .iter()
+                .any(|fields| fields.iter().all(Self::is_catch_all))
+            {
+                continue;
+            }
+
+            // A single field can be covered by several arms, e.g. `Some(true)`
+            // and `Some(false)`
+            if *arity == 1 && !fields.is_empty() {
+                let inner: Vec<&Pattern> =
+                    fields.iter().filter_map(|fields| fields.first()).collect();
+                missing.extend(
+                    self.missing_patterns(&inner)?
+                        .into_iter()
+                        .map(|pattern| format!("{full_name}({pattern})")),
+                );
+                continue;
+            }
+
+            missing.push(format!("{full_name}(..)"));
+        }
+
+        Ok(missing)
+    }
+
+    fn is_catch_all(pattern: &Pattern) -> bool {
+        matches!(pattern, Pattern::Wildcard | Pattern::Binding(_))
+    }
+}
+
+impl Default for Checker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::Parser;
+
+    use super::*;
+
+    fn check(source: &str) -> Result<(), CheckError> {
+        let mut parser = Parser::new(source);
+        let program = parser.parse().unwrap();
+
+        Checker::new().check(&program)
+    }
+
+    #[test]
+    fn exhaustive_enum_match() {
+        let result = check(
+            "
+        enum Shape { Circle(float), Rect(float, float), Empty }
+        func area(shape) {
+            return match shape {
+                Shape::Circle(r) => r * r * 3.14,
+                Shape::Rect(w, h) => w * h,
+                Shape::Empty => 0.0,
+            };
+        }
+        ",
+        );
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn missing_variant() {
+        let result = check(
+            "
+        enum Shape { Circle(float), Rect(float, float), Empty }
+        match Shape::Empty {
+            Shape::Circle(r) => r,
+            Shape::Rect(w, 1.0) => w,
+        }
+        ",
+        );
+        assert_eq!(
+            result,
+            Err(CheckError::NonExhaustiveMatch(vec![
+                "Shape::Rect(..)".to_string(),
+                "Shape::Empty".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn nested_and_catch_all_patterns() {
+        assert_eq!(
+            check(
+                "
+        enum Flag { On(bool), Off }
+        match Flag::Off {
+            Flag::On(true) => 1,
+            Flag::On(false) => 2,
+            Flag::Off => 3,
+        }
+        match 1 {
+            0 => 1,
+            n => n,
+        }
+        "
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            check(
+                "
+        match 1 {
+            0 => 1,
+            1 => 2,
+        }
+        "
+            ),
+            Err(CheckError::NonExhaustiveMatch(vec!["_".to_string()]))
+        );
+        assert_eq!(
+            check(
+                "
+        match true {
+            true => 1,
+        }
+        "
+            ),
+            Err(CheckError::NonExhaustiveMatch(vec!["false".to_string()]))
+        );
+    }
+
+    #[test]
+    fn undefined_variant() {
+        let result = check(
+            "
+        enum Shape { Empty }
+        match Shape::Empty {
+            Shape::Square => 1,
+        }
+        ",
+        );
+        assert_eq!(
+            result,
+            Err(CheckError::UndefinedVariant("Shape::Square".to_string()))
+        );
+    }
+
+    #[test]
+    fn builtin_enums() {
+        assert_eq!(
+            check(
+                "
+        match Some(1) {
+            Some(n) => n,
+        }
+        "
+            ),
+            Err(CheckError::NonExhaustiveMatch(vec![
+                "Option::None".to_string()
+            ]))
+        );
+        assert_eq!(
+            check(
+                "
+        match Ok(1) {
+            Ok(n) => n,
+            Err(_) => 0,
+        }
+        "
+            ),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,310 @@
+use std::{cell::RefCell, fmt::Display, fs, io, path::Path, rc::Rc};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    ast::program::Program,
+    checker::{CheckError, Checker},
+    optimizer::Optimizer,
+    parser::{ParseError, Parser},
+    runtime::{
+        convert::{IntoNativeFn, IntoRuntimeVal},
+        environment::{Env, Environment},
+        interpreter::Interpreter,
+        limits::ExecutionLimits,
+        operators::NumericPolicy,
+        serialization::{from_value, to_value},
+        values::{RuntimeError, RuntimeVal},
+    },
+};
+
+/// Error returned by the `Engine`, one variant per stage a script goes
+/// through.
+#[derive(Debug)]
+pub enum EngineError {
+    Io(io::Error),
+    Parse(ParseError),
+    Check(CheckError),
+    Runtime(RuntimeError),
+}
+
+impl Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::Io(err) => write!(f, "io error: {err}"),
+            EngineError::Parse(err) => write!(f, "parse error: {err}"),
+            EngineError::Check(err) => write!(f, "check error: {err}"),
+            EngineError::Runtime(err) => write!(f, "runtime error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EngineError::Io(err) => Some(err),
+            EngineError::Parse(err) => Some(err),
+            EngineError::Check(err) => Some(err),
+            EngineError::Runtime(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for EngineError {
+    fn from(err: io::Error) -> Self {
+        EngineError::Io(err)
+    }
+}
+
+impl From<ParseError> for EngineError {
+    fn from(err: ParseError) -> Self {
+        EngineError::Parse(err)
+    }
+}
+
+impl From<CheckError> for EngineError {
+    fn from(err: CheckError) -> Self {
+        EngineError::Check(err)
+    }
+}
+
+impl From<RuntimeError> for EngineError {
+    fn from(err: RuntimeError) -> Self {
+        EngineError::Runtime(err)
+    }
+}
+
+/// Entry point for embedding the language in a Rust program.
+///
+/// An engine keeps a single global environment: variables, functions and
+/// types declared by a script stay available to the following `eval` calls
+/// and to the host through `get_global` and `call`.
+pub struct Engine {
+    interpreter: Interpreter,
+    env: Env,
+    optimize: bool,
+}
+
+impl Engine {
+    pub fn new() -> Self {
+        Engine {
+            interpreter: Interpreter::new(),
+            env: Rc::new(RefCell::new(Environment::new(None))),
+            optimize: false,
+        }
+    }
+
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.interpreter = self.interpreter.with_limits(limits);
+        self
+    }
+
+    pub fn with_numeric_policy(mut self, numeric_policy: NumericPolicy) -> Self {
+        self.interpreter = self.interpreter.with_numeric_policy(numeric_policy);
+        self
+    }
+
+    /// Run the optimizer on every script before evaluating it.
+    pub fn with_optimizer(mut self, optimize: bool) -> Self {
+        self.optimize = optimize;
+        self
+    }
+
+    /// Parse and check a script without evaluating it.
+    pub fn compile(&self, source: &str) -> Result<Program, EngineError> {
+        let program = Parser::new(source).parse()?;
+        Checker::new().check(&program)?;
+
+        match self.optimize {
+            true => Ok(Optimizer::new().optimize(program)),
+            false => Ok(program),
+        }
+    }
+
+    /// Evaluate a compiled script in the global environment.
+    pub fn run(&self, program: &Program) -> Result<RuntimeVal, EngineError> {
+        Ok(self
+            .interpreter
+            .evaluate_program(program, Rc::clone(&self.env))?)
+    }
+
+    /// Evaluate a script, the result is the value of its last statement
+    /// (`RuntimeVal::Undefined` for a declaration).
+    pub fn eval(&self, source: &str) -> Result<RuntimeVal, EngineError> {
+        let program = self.compile(source)?;
+        self.run(&program)
+    }
+
+    pub fn eval_file(&self, path: impl AsRef<Path>) -> Result<RuntimeVal, EngineError> {
+        let source = fs::read_to_string(path)?;
+        self.eval(&source)
+    }
+
+    /// Declare a global variable, or replace the value of an existing one.
+    /// Takes a script value or a primitive such as an `i64` or a `String`, see
+    /// `set_global_serde` for any other Rust value.
+    pub fn set_global(&self, name: &str, value: impl IntoRuntimeVal) -> Result<(), EngineError> {
+        let value = value.into_runtime_val();
+        let mut env = self.env.borrow_mut();
+        match env.get_var(name) {
+            Ok(_) => env.assign_var(name, value)?,
+            Err(_) => env.declare_var(name, value, false)?,
+        }
+
+        Ok(())
+    }
+
+    /// Like `set_global` for any serializable Rust value, converted with
+    /// `to_value`.
+    pub fn set_global_serde<T: Serialize + ?Sized>(
+        &self,
+        name: &str,
+        value: &T,
+    ) -> Result<(), EngineError> {
+        self.set_global(name, to_value(value)?)
+    }
+
+    pub fn get_global(&self, name: &str) -> Result<RuntimeVal, EngineError> {
+        Ok(self.env.borrow().get_var(name)?)
+    }
+
+    /// Read a global variable as any deserializable Rust value, converted
+    /// with `from_value`.
+    pub fn get_global_as<T: DeserializeOwned>(&self, name: &str) -> Result<T, EngineError> {
+        Ok(from_value(self.get_global(name)?)?)
+    }
+
+    /// Call a global function, declared by a script or registered by the host.
+    pub fn call(&self, name: &str, args: Vec<RuntimeVal>) -> Result<RuntimeVal, EngineError> {
+        let (func, scope) = Environment::lookup_func(&self.env, name)?;
+        Ok(self.interpreter.call_function(name, func, args, scope)?)
+    }
+
+    /// Expose a Rust function to scripts, see `Environment::register_native`.
+    pub fn register_native(
+        &self,
+        name: &str,
+        arity: usize,
+        func: impl Fn(&[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> + 'static,
+    ) {
+        self.env.borrow_mut().register_native(name, arity, func);
+    }
+
+    /// Expose a typed Rust closure to scripts, see `Environment::register_fn`.
+    pub fn register_fn<Args>(&self, name: &str, func: impl IntoNativeFn<Args>) {
+        self.env.borrow_mut().register_fn(name, func);
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn globals_persist_between_evaluations() {
+        let engine = Engine::new();
+        engine
+            .eval("let count: int = 1; func double(n) { return n * 2; }")
+            .expect("Failed to evaluate");
+
+        assert_eq!(
+            engine
+                .eval("double(count) + 1")
+                .expect("Failed to evaluate"),
+            RuntimeVal::Int(3)
+        );
+    }
+
+    #[test]
+    fn set_and_get_globals() {
+        let engine = Engine::new();
+        engine
+            .set_global("name", RuntimeVal::String("world".to_string()))
+            .expect("Failed to set global");
+        engine
+            .eval("let greeting: string = \"hello \" + name;")
+            .expect("Failed to evaluate");
+        assert_eq!(
+            engine.get_global("greeting").expect("Failed to get global"),
+            RuntimeVal::String("hello world".to_string())
+        );
+
+        // An existing global is replaced, constants stay constant
+        engine
+            .set_global("name", RuntimeVal::Int(1))
+            .expect("Failed to set global");
+        assert_eq!(engine.get_global("name").unwrap(), RuntimeVal::Int(1));
+        engine.eval("const limit: int = 3;").unwrap();
+        assert!(matches!(
+            engine.set_global("limit", RuntimeVal::Int(4)),
+            Err(EngineError::Runtime(RuntimeError::ConstantReassignment(_)))
+        ));
+        assert!(matches!(
+            engine.get_global("missing"),
+            Err(EngineError::Runtime(RuntimeError::UndefinedVariable(_)))
+        ));
+    }
+
+    #[test]
+    fn call_functions() {
+        let engine = Engine::new();
+        engine
+            .eval("func add(a, b) { return a + b; }")
+            .expect("Failed to evaluate");
+        engine.register_native("negate", 1, |args| match &args[0] {
+            RuntimeVal::Int(n) => Ok(RuntimeVal::Int(-n)),
+            _ => Err(RuntimeError::InvalidType),
+        });
+
+        assert_eq!(
+            engine
+                .call("add", vec![RuntimeVal::Int(1), RuntimeVal::Int(2)])
+                .expect("Failed to call"),
+            RuntimeVal::Int(3)
+        );
+        assert_eq!(
+            engine
+                .call("negate", vec![RuntimeVal::Int(1)])
+                .expect("Failed to call"),
+            RuntimeVal::Int(-1)
+        );
+        assert!(matches!(
+            engine.call("add", vec![]),
+            Err(EngineError::Runtime(
+                RuntimeError::InvalidFuncCallParametersCount(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn typed_errors() {
+        let engine = Engine::new();
+
+        match engine.eval("let x: int = 1 +;") {
+            Err(EngineError::Parse(err)) => assert_eq!((err.line, err.column), (1, 17)),
+            result => panic!("Expected a parse error, got {:?}", result),
+        }
+        assert!(matches!(
+            engine.eval("let x: int = 1 # 2;"),
+            Err(EngineError::Parse(_))
+        ));
+        assert!(matches!(
+            engine.eval("match true { true => 1 }"),
+            Err(EngineError::Check(CheckError::NonExhaustiveMatch(_)))
+        ));
+        assert!(matches!(
+            engine.eval("1 / 0"),
+            Err(EngineError::Runtime(RuntimeError::DivisionByZero))
+        ));
+        assert!(matches!(
+            engine.eval_file("missing.tr"),
+            Err(EngineError::Io(_))
+        ));
+    }
+}
@@ -1,9 +1,11 @@
 pub mod tokenizer;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     // Literals
     Integer,
+    BigInt,
     Float,
     String,
     Bool,
@@ -13,14 +15,38 @@ pub enum TokenType {
     Plus,
     Minus,
     Star,
+    StarStar,
     Slash,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    LessLess,
+    GreaterGreater,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
     OpenParen,
     CloseParen,
     SemiColon,
     OpenBrace,
     CloseBrace,
+    OpenBracket,
+    CloseBracket,
     Comma,
     Colon,
+    DoubleColon,
+    Dot,
+    FatArrow,
+    Question,
+    EqualEqual,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
 
     // Keywords
     Let,
@@ -28,10 +54,23 @@ pub enum TokenType {
     Func,
     Return,
     Print,      // print
+    If,         // if
+    Else,       // else
+    Struct,     // struct
+    Impl,       // impl
+    Enum,       // enum
+    Match,      // match
+    Throw,      // throw
+    Try,        // try
+    Catch,      // catch
+    Finally,    // finally
     IntType,    // int
+    BigIntType, // bigint
     FloatType,  // float
     StringType, // string
     BoolType,   // bool
+    ListType,   // list
+    MapType,    // map
 
     // Special tokens
     Comment,
@@ -39,6 +78,8 @@ pub enum TokenType {
     Skipped,
     EOL,
     EOF,
+    // A character no token starts with, reported by the parser
+    Invalid,
 }
 
 #[derive(Debug, Clone, PartialEq)]
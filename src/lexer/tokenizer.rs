@@ -2,36 +2,74 @@ use super::{Token, TokenType};
 use regex::Regex;
 
 const SPECS: &[(TokenType, &str)] = &[
+    (TokenType::BigInt, r"^(?P<raw>\d+)n\b"),
     (TokenType::Float, r"^\d+\.(\d+)?"),
     (TokenType::Integer, r"^\d+"),
     (TokenType::String, r"^'(?P<raw>[^']*)'"),
     (TokenType::String, "^\"(?P<raw>[^\"]*)\""),
-    (TokenType::Bool, "^(true|false)"),
+    (TokenType::Bool, r"^(true|false)\b"),
     // Comment
     (TokenType::Comment, r"^//.*"),
     // Symbols
+    (TokenType::EqualEqual, r"^=="),
+    (TokenType::FatArrow, r"^=>"),
+    (TokenType::BangEqual, r"^!="),
+    (TokenType::PlusEqual, r"^\+="),
+    (TokenType::MinusEqual, r"^-="),
+    (TokenType::StarEqual, r"^\*="),
+    (TokenType::SlashEqual, r"^/="),
+    (TokenType::PercentEqual, r"^%="),
+    (TokenType::StarStar, r"^\*\*"),
+    (TokenType::LessLess, r"^<<"),
+    (TokenType::GreaterGreater, r"^>>"),
+    (TokenType::LessEqual, r"^<="),
+    (TokenType::GreaterEqual, r"^>="),
+    (TokenType::Less, r"^<"),
+    (TokenType::Greater, r"^>"),
     (TokenType::Equal, r"^="),
     (TokenType::Plus, r"^\+"),
     (TokenType::Minus, r"^-"),
     (TokenType::Star, r"^\*"),
     (TokenType::Slash, r"^/"),
+    (TokenType::Percent, r"^%"),
+    (TokenType::Ampersand, r"^&"),
+    (TokenType::Pipe, r"^\|"),
+    (TokenType::Caret, r"^\^"),
     (TokenType::OpenParen, r"^\("),
     (TokenType::CloseParen, r"^\)"),
     (TokenType::SemiColon, r"^;"),
     (TokenType::OpenBrace, r"^\{"),
     (TokenType::CloseBrace, r"^}"),
+    (TokenType::OpenBracket, r"^\["),
+    (TokenType::CloseBracket, r"^\]"),
     (TokenType::Comma, r"^,"),
+    (TokenType::DoubleColon, r"^::"),
     (TokenType::Colon, r"^:"),
+    (TokenType::Dot, r"^\."),
+    (TokenType::Question, r"^\?"),
     // Keywords
-    (TokenType::Let, r"^let"),
-    (TokenType::Const, r"^const"),
-    (TokenType::Func, r"^func"),
-    (TokenType::Return, r"^return"),
-    (TokenType::Print, r"^print"),
-    (TokenType::IntType, r"^int"),
-    (TokenType::FloatType, r"^float"),
-    (TokenType::StringType, r"^string"),
-    (TokenType::BoolType, r"^bool"),
+    (TokenType::Let, r"^let\b"),
+    (TokenType::Const, r"^const\b"),
+    (TokenType::Func, r"^func\b"),
+    (TokenType::Return, r"^return\b"),
+    (TokenType::Print, r"^print\b"),
+    (TokenType::If, r"^if\b"),
+    (TokenType::Else, r"^else\b"),
+    (TokenType::Struct, r"^struct\b"),
+    (TokenType::Impl, r"^impl\b"),
+    (TokenType::Enum, r"^enum\b"),
+    (TokenType::Match, r"^match\b"),
+    (TokenType::Throw, r"^throw\b"),
+    (TokenType::Try, r"^try\b"),
+    (TokenType::Catch, r"^catch\b"),
+    (TokenType::Finally, r"^finally\b"),
+    (TokenType::IntType, r"^int\b"),
+    (TokenType::BigIntType, r"^bigint\b"),
+    (TokenType::FloatType, r"^float\b"),
+    (TokenType::StringType, r"^string\b"),
+    (TokenType::BoolType, r"^bool\b"),
+    (TokenType::ListType, r"^list\b"),
+    (TokenType::MapType, r"^map\b"),
     // Special tokens
     (TokenType::Identifier, r"^[a-zA-Z_][a-zA-Z0-9_]*"),
     (TokenType::EOL, r"^\n+"),
@@ -68,26 +106,18 @@ impl Tokenizer {
     }
 
     fn match_string(&self, re: &Regex, input: &str) -> (usize, Option<String>) {
-        let captures = re.captures_iter(input);
-
-        let length: usize;
-
-        let find = re.find(input);
-        match find {
-            Some(m) => {
-                length = m.end() - m.start();
-            }
+        let capture = match re.captures(input) {
+            Some(capture) => capture,
             None => return (0, None),
         };
 
-        for capture in captures {
-            match capture.name("raw") {
-                Some(m) => return (length, Some(m.as_str().to_string())),
-                None => return (length, Some(capture.get(0).unwrap().as_str().to_string())),
-            }
-        }
+        let whole = capture.get(0).unwrap();
+        let length = whole.end() - whole.start();
 
-        (length, None)
+        match capture.name("raw") {
+            Some(m) => (length, Some(m.as_str().to_string())),
+            None => (length, Some(whole.as_str().to_string())),
+        }
     }
 }
 
@@ -136,12 +166,19 @@ impl Iterator for Tokenizer {
         }
 
         // If we reach this point, it means that we have no match for the current character
-        if self.position < self.source.len() {
-            panic!(
-                "Unexpected character: {} at position {}",
-                &self.source[self.position..self.position + 1],
-                self.position
-            );
+        if let Some(character) = substr.unwrap().chars().next() {
+            let length = character.len_utf8();
+            let tok = Some(Token {
+                kind: TokenType::Invalid,
+                value: character.to_string(),
+                line: self.line,
+                column: self.offset,
+                end_column: self.offset + 1,
+            });
+            self.position += length;
+            self.offset += 1;
+
+            return tok;
         }
 
         None
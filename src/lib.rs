@@ -0,0 +1,18 @@
+pub mod ast;
+pub mod bigint;
+pub mod checker;
+pub mod engine;
+pub mod lexer;
+pub mod optimizer;
+pub mod parser;
+pub mod runtime;
+mod stack;
+
+pub use engine::{Engine, EngineError};
+pub use runtime::{
+    convert::{FromRuntimeVal, IntoRuntimeVal},
+    limits::ExecutionLimits,
+    operators::NumericPolicy,
+    serialization::{from_value, to_value},
+    values::{RuntimeError, RuntimeVal},
+};
@@ -1,41 +1,70 @@
-use crate::{
-    parser::Parser,
-    runtime::{environment::Environment, interpreter::Interpreter},
-};
+use lang::{Engine, ExecutionLimits, NumericPolicy};
 use std::{
-    cell::RefCell,
     env::{self, args},
     fs,
-    rc::Rc,
+    str::FromStr,
+    time::Duration,
 };
 
-mod ast;
-mod lexer;
-mod parser;
-mod runtime;
-
 fn main() {
     let is_debug = match env::var("TR_DEBUG") {
         Ok(value) => value == "1",
         Err(_) => false,
     };
 
-    let file = args().nth(1).expect("No file provided");
+    let args: Vec<String> = args().skip(1).collect();
+    let is_optimized = args.iter().any(|arg| arg == "-O");
+    let is_dump = args.iter().any(|arg| arg == "--dump-ast");
+
+    let file = args
+        .iter()
+        .find(|arg| !arg.starts_with('-'))
+        .expect("No file provided");
     let content = fs::read_to_string(file).expect("Unable to read file");
 
-    let mut parser = Parser::new(content.as_str());
-    let program = parser.parse();
+    let mut limits = ExecutionLimits {
+        max_steps: env_setting("TR_MAX_STEPS"),
+        max_duration: env_setting("TR_TIMEOUT_MS").map(Duration::from_millis),
+        max_string_bytes: env_setting("TR_MAX_STRING_BYTES"),
+        ..Default::default()
+    };
+    if let Some(max_call_depth) = env_setting("TR_MAX_CALL_DEPTH") {
+        limits.max_call_depth = max_call_depth;
+    }
+    let numeric_policy: NumericPolicy = env_setting("TR_NUMERIC_POLICY").unwrap_or_default();
+    let engine = Engine::new()
+        .with_limits(limits)
+        .with_numeric_policy(numeric_policy)
+        .with_optimizer(is_optimized);
+
+    let program = match engine.compile(&content) {
+        Ok(program) => program,
+        Err(err) => {
+            println!("{err}");
+            return;
+        }
+    };
+
+    // The program as the engine would run it, after the optimizer with -O
+    if is_dump {
+        print!("{}", program.dump());
+        return;
+    }
 
     if is_debug {
         println!("{:#?}", program);
         println!("----- Result -----");
     }
 
-    let env = Rc::new(RefCell::new(Environment::new(None)));
-    let runtime = Interpreter {};
-
-    let result = runtime.evaluate_program(&program, env);
+    let result = engine.run(&program);
     if is_debug || result.is_err() {
         println!("{:#?}", result);
     }
 }
+
+/// Read an optional setting from the environment.
+fn env_setting<T: FromStr>(name: &str) -> Option<T> {
+    env::var(name)
+        .ok()
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("Invalid {name}")))
+}
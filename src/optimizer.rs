@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{atom::Atom, expr::Expr, program::Program, stmt::Stmt, types::Type},
+    runtime::{
+        operators::{self, NumericPolicy},
+        values::RuntimeVal,
+    },
+    stack::ensure_sufficient_stack,
+};
+
+/// AST level optimizer, run between parsing and evaluation when `-O` is given.
+///
+/// It folds constant expressions, propagates `const` bindings initialised with
+/// a literal and removes dead statements following a `return` in a block.
+/// Expressions whose evaluation fails (e.g. `1 / 0`) are left untouched so the
+/// error is still raised at runtime.
+pub struct Optimizer {
+    // One map per lexical scope, `None` marks a non constant (shadowing) binding
+    scopes: Vec<HashMap<String, Option<Atom>>>,
+}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        Optimizer {
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    pub fn optimize(&mut self, program: Program) -> Program {
+        let statements = program
+            .statements
+            .into_iter()
+            .map(|stmt| self.optimize_stmt(stmt))
+            .collect();
+
+        Program { statements }
+    }
+
+    fn optimize_stmt(&mut self, stmt: Stmt) -> Stmt {
+        ensure_sufficient_stack(|| match stmt {
+            Stmt::VarDeclaration(name, typing, is_const, expr) => {
+                let expr = self.optimize_expr(expr);
+                let value = match &expr {
+                    Expr::Literal(atom) if is_const && Self::matches_type(atom, &typing) => {
+                        Some(atom.clone())
+                    }
+                    _ => None,
+                };
+                self.declare(&name, value);
+
+                Stmt::VarDeclaration(name, typing, is_const, expr)
+            }
+            Stmt::FuncDeclaration(name, parameters, body) => {
+                // Parameters shadow the constants of the declaring scope
+                self.scopes.push(HashMap::new());
+                for parameter in &parameters {
+                    self.declare(parameter, None);
+                }
+                let body = self.optimize_stmt(*body);
+                self.scopes.pop();
+
+                Stmt::FuncDeclaration(name, parameters, Box::new(body))
+            }
+            Stmt::ImplDeclaration(type_name, methods) => Stmt::ImplDeclaration(
+                type_name,
+                methods
+                    .into_iter()
+                    .map(|method| self.optimize_stmt(method))
+                    .collect(),
+            ),
+            Stmt::StructDeclaration(..) | Stmt::EnumDeclaration(..) => stmt,
+            Stmt::Block(stmts) => {
+                self.scopes.push(HashMap::new());
+                let stmts = self.optimize_block(stmts);
+                self.scopes.pop();
+
+                Stmt::Block(stmts)
+            }
+            Stmt::If(condition, then_branch, else_branch) => Stmt::If(
+                self.optimize_expr(condition),
+                Box::new(self.optimize_stmt(*then_branch)),
+                else_branch.map(|branch| Box::new(self.optimize_stmt(*branch))),
+            ),
+            Stmt::Try(body, catch, finally) => Stmt::Try(
+                Box::new(self.optimize_stmt(*body)),
+                catch.map(|(name, block)| {
+                    // The caught value shadows the constants
+                    self.scopes.push(HashMap::new());
+                    self.declare(&name, None);
+                    let block = self.optimize_stmt(*block);
+                    self.scopes.pop();
+
+                    (name, Box::new(block))
+                }),
+                finally.map(|block| Box::new(self.optimize_stmt(*block))),
+            ),
+            Stmt::Throw(expr) => Stmt::Throw(self.optimize_expr(expr)),
+            Stmt::Return(expr) => Stmt::Return(self.optimize_expr(expr)),
+            Stmt::Print(expr) => Stmt::Print(self.optimize_expr(expr)),
+            Stmt::Expression(expr) => Stmt::Expression(self.optimize_expr(expr)),
+        })
+    }
+
+    fn optimize_block(&mut self, stmts: Vec<Stmt>) -> Vec<Stmt> {
+        let mut optimized = vec![];
+
+        for stmt in stmts {
+            let stmt = self.optimize_stmt(stmt);
+            let is_return = matches!(stmt, Stmt::Return(_));
+            optimized.push(stmt);
+
+            // Anything after an unconditional return is never evaluated
+            if is_return {
+                break;
+            }
+        }
+
+        optimized
+    }
+
+    fn optimize_expr(&mut self, expr: Expr) -> Expr {
+        ensure_sufficient_stack(|| match expr {
+            Expr::Binary(lhs, op, rhs) => {
+                let lhs = self.optimize_expr(*lhs);
+                let rhs = self.optimize_expr(*rhs);
+
+                if let (Expr::Literal(left), Expr::Literal(right)) = (&lhs, &rhs) {
+                    // Results that are an error under the strict policy are
+                    // left to the runtime, whatever policy it uses
+                    let folded = operators::binary_op(
+                        RuntimeVal::from(left),
+                        &op,
+                        RuntimeVal::from(right),
+                        NumericPolicy::Strict,
+                    );
+                    if let Some(atom) = folded.ok().as_ref().and_then(Self::to_atom) {
+                        return Expr::Literal(atom);
+                    }
+                }
+
+                Expr::Binary(Box::new(lhs), op, Box::new(rhs))
+            }
+            // The assigned target must stay as is, only indexes and the value can be folded
+            Expr::Assignment(lhs, op, rhs) => {
+                let lhs = match *lhs {
+                    Expr::Index(target, index) => {
+                        Expr::Index(target, Box::new(self.optimize_expr(*index)))
+                    }
+                    lhs => lhs,
+                };
+
+                Expr::Assignment(Box::new(lhs), op, Box::new(self.optimize_expr(*rhs)))
+            }
+            Expr::List(elements) => Expr::List(
+                elements
+                    .into_iter()
+                    .map(|element| self.optimize_expr(element))
+                    .collect(),
+            ),
+            Expr::Map(entries) => Expr::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (self.optimize_expr(key), self.optimize_expr(value)))
+                    .collect(),
+            ),
+            Expr::Index(target, index) => Expr::Index(
+                Box::new(self.optimize_expr(*target)),
+                Box::new(self.optimize_expr(*index)),
+            ),
+            Expr::StructLiteral(name, fields) => Expr::StructLiteral(
+                name,
+                fields
+                    .into_iter()
+                    .map(|(field, value)| (field, self.optimize_expr(value)))
+                    .collect(),
+            ),
+            Expr::Member(target, field) => {
+                Expr::Member(Box::new(self.optimize_expr(*target)), field)
+            }
+            Expr::Try(target) => Expr::Try(Box::new(self.optimize_expr(*target))),
+            Expr::MethodCall(target, method, args) => Expr::MethodCall(
+                Box::new(self.optimize_expr(*target)),
+                method,
+                args.into_iter()
+                    .map(|arg| self.optimize_expr(arg))
+                    .collect(),
+            ),
+            Expr::CallExpr(name, args) => Expr::CallExpr(
+                name,
+                args.into_iter()
+                    .map(|arg| self.optimize_expr(arg))
+                    .collect(),
+            ),
+            Expr::Variant(name, variant, args) => Expr::Variant(
+                name,
+                variant,
+                args.into_iter()
+                    .map(|arg| self.optimize_expr(arg))
+                    .collect(),
+            ),
+            Expr::Match(subject, arms) => {
+                let subject = self.optimize_expr(*subject);
+                let arms = arms
+                    .into_iter()
+                    .map(|(pattern, expr)| {
+                        // Bindings of the pattern shadow the constants
+                        self.scopes.push(HashMap::new());
+                        for name in pattern.bindings() {
+                            self.declare(name, None);
+                        }
+                        let expr = self.optimize_expr(expr);
+                        self.scopes.pop();
+
+                        (pattern, expr)
+                    })
+                    .collect();
+
+                Expr::Match(Box::new(subject), arms)
+            }
+            Expr::Identifier(name) => match self.lookup(&name) {
+                Some(atom) => Expr::Literal(atom),
+                None => Expr::Identifier(name),
+            },
+            Expr::Literal(_) => expr,
+        })
+    }
+
+    fn declare(&mut self, name: &str, value: Option<Atom>) {
+        self.scopes
+            .last_mut()
+            .expect("Optimizer has no scope")
+            .insert(name.to_string(), value);
+    }
+
+    fn lookup(&self, name: &str) -> Option<Atom> {
+        for scope in self.scopes.iter().rev() {
+            if let Some(value) = scope.get(name) {
+                return value.clone();
+            }
+        }
+
+        None
+    }
+
+    fn matches_type(atom: &Atom, typing: &Type) -> bool {
+        matches!(
+            (atom, typing),
+            (Atom::Integer(_), Type::Int)
+                | (Atom::BigInt(_), Type::BigInt)
+                | (Atom::Float(_), Type::Float)
+                | (Atom::String(_), Type::String)
+                | (Atom::Bool(_), Type::Bool)
+        )
+    }
+
+    fn to_atom(val: &RuntimeVal) -> Option<Atom> {
+        match val {
+            RuntimeVal::Int(num) => Some(Atom::Integer(*num)),
+            RuntimeVal::BigInt(num) => Some(Atom::BigInt(num.clone())),
+            RuntimeVal::Float(num) => Some(Atom::Float(*num)),
+            RuntimeVal::String(string) => Some(Atom::String(string.clone())),
+            RuntimeVal::Bool(val) => Some(Atom::Bool(*val)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{lexer::TokenType, parser::Parser};
+
+    use super::*;
+
+    fn optimize(source: &str) -> Vec<Stmt> {
+        let mut parser = Parser::new(source);
+        let program = parser.parse().unwrap();
+
+        Optimizer::new().optimize(program).statements
+    }
+
+    #[test]
+    fn fold_arithmetic() {
+        let statements = optimize("60 * 60 * 24");
+
+        assert_eq!(
+            statements[0],
+            Stmt::Expression(Expr::Literal(Atom::Integer(86400)))
+        );
+    }
+
+    #[test]
+    fn fold_string_concatenation() {
+        let statements = optimize("'hello, ' + 'world!'");
+
+        assert_eq!(
+            statements[0],
+            Stmt::Expression(Expr::Literal(Atom::String("hello, world!".to_string())))
+        );
+    }
+
+    #[test]
+    fn keep_division_by_zero() {
+        let statements = optimize("1 + 2 / 0");
+
+        assert_eq!(
+            statements[0],
+            Stmt::Expression(Expr::Binary(
+                Box::new(Expr::Literal(Atom::Integer(1))),
+                TokenType::Plus,
+                Box::new(Expr::Binary(
+                    Box::new(Expr::Literal(Atom::Integer(2))),
+                    TokenType::Slash,
+                    Box::new(Expr::Literal(Atom::Integer(0)))
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn propagate_const() {
+        let statements = optimize(
+            "
+        const day: int = 60 * 60 * 24;
+        let week: int = day * 7;
+        ",
+        );
+
+        assert_eq!(
+            statements[1],
+            Stmt::VarDeclaration(
+                "week".to_string(),
+                Type::Int,
+                false,
+                Expr::Literal(Atom::Integer(604800))
+            )
+        );
+    }
+
+    #[test]
+    fn do_not_propagate_shadowed_const() {
+        let statements = optimize(
+            "
+        const x: int = 1;
+        {
+            let x: int = 2;
+            x = 3;
+            print x;
+        }
+        ",
+        );
+
+        match &statements[1] {
+            Stmt::Block(stmts) => {
+                assert_eq!(stmts[2], Stmt::Print(Expr::Identifier("x".to_string())))
+            }
+            _ => panic!("Expected block"),
+        }
+    }
+
+    #[test]
+    fn propagate_into_functions() {
+        let statements = optimize(
+            "
+        const x: int = 1;
+        const y: int = 2;
+        func test(y) {
+            return x + y;
+        }
+        ",
+        );
+
+        assert_eq!(
+            statements[2],
+            Stmt::FuncDeclaration(
+                "test".to_string(),
+                vec!["y".to_string()],
+                Box::new(Stmt::Block(vec![Stmt::Return(Expr::Binary(
+                    Box::new(Expr::Literal(Atom::Integer(1))),
+                    TokenType::Plus,
+                    Box::new(Expr::Identifier("y".to_string()))
+                ))]))
+            )
+        );
+    }
+
+    #[test]
+    fn remove_dead_code_after_return() {
+        let statements = optimize(
+            "
+        {
+            return 1;
+            print 2;
+        }
+        ",
+        );
+
+        assert_eq!(
+            statements[0],
+            Stmt::Block(vec![Stmt::Return(Expr::Literal(Atom::Integer(1)))])
+        );
+    }
+
+    #[test]
+    fn keep_integer_overflow() {
+        let statements = optimize("9223372036854775807 + 1");
+
+        assert_eq!(
+            statements[0],
+            Stmt::Expression(Expr::Binary(
+                Box::new(Expr::Literal(Atom::Integer(i64::MAX))),
+                TokenType::Plus,
+                Box::new(Expr::Literal(Atom::Integer(1)))
+            ))
+        );
+    }
+
+    #[test]
+    fn keep_float_division_by_zero() {
+        let statements = optimize("1.0 / 0.0");
+
+        assert_eq!(
+            statements[0],
+            Stmt::Expression(Expr::Binary(
+                Box::new(Expr::Literal(Atom::Float(1.0))),
+                TokenType::Slash,
+                Box::new(Expr::Literal(Atom::Float(0.0)))
+            ))
+        );
+    }
+}
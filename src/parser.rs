@@ -1,14 +1,63 @@
-use std::vec;
+use std::{fmt::Display, vec};
 
 use crate::{
-    ast::{atom::Atom, expr::Expr, program::Program, stmt::Stmt},
+    ast::{atom::Atom, expr::Expr, pattern::Pattern, program::Program, stmt::Stmt, types::Type},
     eof_token,
     lexer::{tokenizer::Tokenizer, Token, TokenType},
+    stack::ensure_sufficient_stack,
 };
 
+/// A syntax error, `line` and `column` are those of the offending token and
+/// start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl ParseError {
+    fn new(token: &Token, message: String) -> Self {
+        ParseError {
+            message,
+            line: token.line + 1,
+            column: token.column + 1,
+        }
+    }
+
+    fn unexpected(token: &Token) -> Self {
+        match token.kind {
+            TokenType::Invalid => {
+                ParseError::new(token, format!("unexpected character {:?}", token.value))
+            }
+            _ => ParseError::new(token, format!("unexpected token {:?}", token.kind)),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {} and column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Deepest nesting of statements, expressions, types and patterns accepted,
+/// the checker, the optimizer and the interpreter recurse once per level.
+const MAX_NESTING: usize = 256;
+
 pub struct Parser {
     tokens: Vec<Token>,
     previous: Option<Token>,
+    // Set while parsing an `if` condition, where `name {` opens the branch
+    no_struct_literal: bool,
+    // Nesting of the construct being parsed, see `MAX_NESTING`
+    depth: usize,
 }
 
 impl Parser {
@@ -21,22 +70,24 @@ impl Parser {
         Parser {
             tokens,
             previous: None,
+            no_struct_literal: false,
+            depth: 0,
         }
     }
 
-    pub fn parse(&mut self) -> Program {
+    pub fn parse(&mut self) -> Result<Program, ParseError> {
         let mut statements: Vec<Stmt> = vec![];
 
         while self.peek().kind != TokenType::EOF {
             if self.peek().kind == TokenType::Comment {
-                self.eat(TokenType::Comment);
+                self.eat(TokenType::Comment)?;
                 continue;
             }
 
-            statements.push(self.parse_statement());
+            statements.push(self.parse_statement()?);
         }
 
-        Program { statements }
+        Ok(Program { statements })
     }
 
     fn peek(&self) -> Token {
@@ -53,18 +104,29 @@ impl Parser {
         }
     }
 
-    fn eat(&mut self, token_type: TokenType) -> Token {
+    /// Look `n` tokens past the current one without consuming anything.
+    fn peek_nth(&self, n: usize) -> TokenType {
+        match self.tokens.len().checked_sub(n + 1) {
+            Some(index) => self.tokens[index].kind.clone(),
+            None => TokenType::EOF,
+        }
+    }
+
+    fn eat(&mut self, token_type: TokenType) -> Result<Token, ParseError> {
         let token = self.advance();
+        if token.kind == TokenType::Invalid {
+            return Err(ParseError::unexpected(&token));
+        }
         if token.kind != token_type {
-            panic!(
-                "Expected {:?}, got {:?} at line {} and column {}",
-                token_type, token.kind, token.line, token.end_column,
-            );
+            return Err(ParseError::new(
+                &token,
+                format!("expected {:?}, got {:?}", token_type, token.kind),
+            ));
         }
 
         self.previous = Some(token.clone());
 
-        return token;
+        Ok(token)
     }
 
     fn advance(&mut self) -> Token {
@@ -77,186 +139,759 @@ impl Parser {
         }
     }
 
-    fn parse_statement(&mut self) -> Stmt {
-        match self.peek().kind {
-            TokenType::Let => self.parse_var_declaration(),
-            TokenType::Const => self.parse_var_declaration(),
-            TokenType::Func => self.parse_func_declaration(),
-            TokenType::Return => self.parse_return_stmt(),
-            TokenType::OpenBrace => self.parse_block_stmt(),
-            TokenType::Print => self.parse_print_stmt(),
-            _ => Stmt::Expression(self.parse_expression()),
+    /// Error for the current token, which starts nothing valid here.
+    fn unexpected(&self) -> ParseError {
+        ParseError::unexpected(&self.peek())
+    }
+
+    /// Go one level deeper, for constructs nesting without a call to
+    /// `nested`, like operator chains. The enclosing `nested` call restores
+    /// the depth.
+    fn nest(&mut self) -> Result<(), ParseError> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err(ParseError::new(
+                &self.peek(),
+                format!("nesting deeper than {} levels", MAX_NESTING),
+            ));
         }
+
+        Ok(())
+    }
+
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        let depth = self.depth;
+        let result = self
+            .nest()
+            .and_then(|_| ensure_sufficient_stack(|| parse(self)));
+        self.depth = depth;
+
+        result
+    }
+
+    fn parse_statement(&mut self) -> Result<Stmt, ParseError> {
+        self.nested(|parser| match parser.peek().kind {
+            TokenType::Let => parser.parse_var_declaration(),
+            TokenType::Const => parser.parse_var_declaration(),
+            TokenType::Func => parser.parse_func_declaration(),
+            TokenType::Struct => parser.parse_struct_declaration(),
+            TokenType::Impl => parser.parse_impl_declaration(),
+            TokenType::Enum => parser.parse_enum_declaration(),
+            TokenType::Return => parser.parse_return_stmt(),
+            TokenType::OpenBrace if !parser.is_map_literal() => parser.parse_block_stmt(),
+            TokenType::Print => parser.parse_print_stmt(),
+            TokenType::If => parser.parse_if_stmt(),
+            TokenType::Throw => parser.parse_throw_stmt(),
+            TokenType::Try => parser.parse_try_stmt(),
+            _ => parser.parse_expression_stmt(),
+        })
+    }
+
+    /// A `{` starts a map literal rather than a block when it is followed by
+    /// `key :`, which can't begin a statement.
+    fn is_map_literal(&self) -> bool {
+        matches!(
+            self.peek_nth(1),
+            TokenType::String
+                | TokenType::Integer
+                | TokenType::Float
+                | TokenType::Bool
+                | TokenType::Identifier
+        ) && self.peek_nth(2) == TokenType::Colon
+    }
+
+    fn parse_expression_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let expr = self.parse_expression()?;
+
+        // The trailing semicolon is optional for expression statements
+        if self.peek().kind == TokenType::SemiColon {
+            self.eat(TokenType::SemiColon)?;
+        }
+
+        Ok(Stmt::Expression(expr))
     }
 
-    fn parse_expression(&mut self) -> Expr {
-        self.parse_assignment_expr()
+    fn parse_expression(&mut self) -> Result<Expr, ParseError> {
+        self.nested(Self::parse_assignment_expr)
     }
 
-    fn parse_print_stmt(&mut self) -> Stmt {
-        self.eat(TokenType::Print);
-        let expr = self.parse_expression();
+    fn parse_print_stmt(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::Print)?;
+        let expr = self.parse_expression()?;
 
-        self.eat(TokenType::SemiColon);
+        self.eat(TokenType::SemiColon)?;
 
-        Stmt::Print(expr)
+        Ok(Stmt::Print(expr))
     }
 
-    fn parse_block_stmt(&mut self) -> Stmt {
-        self.eat(TokenType::OpenBrace);
+    fn parse_block_stmt(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::OpenBrace)?;
         let mut statements: Vec<Stmt> = vec![];
         while self.peek().kind != TokenType::CloseBrace {
-            statements.push(self.parse_statement());
+            statements.push(self.parse_statement()?);
         }
-        self.eat(TokenType::CloseBrace);
+        self.eat(TokenType::CloseBrace)?;
+
+        Ok(Stmt::Block(statements))
+    }
+
+    fn parse_if_stmt(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::If)?;
+        let condition = self.parse_condition()?;
+        let then_branch = self.parse_block_stmt()?;
+
+        let mut else_branch = None;
+        if self.peek().kind == TokenType::Else {
+            self.eat(TokenType::Else)?;
+            else_branch = match self.peek().kind {
+                TokenType::If => Some(Box::new(self.nested(Self::parse_if_stmt)?)),
+                _ => Some(Box::new(self.parse_block_stmt()?)),
+            };
+        }
+
+        Ok(Stmt::If(condition, Box::new(then_branch), else_branch))
+    }
+
+    /// Parse an expression directly followed by a block, struct literals are
+    /// only allowed there inside parentheses.
+    fn parse_condition(&mut self) -> Result<Expr, ParseError> {
+        let no_struct_literal = self.no_struct_literal;
+        self.no_struct_literal = true;
+        let condition = self.parse_expression();
+        self.no_struct_literal = no_struct_literal;
+
+        condition
+    }
+
+    fn parse_return_stmt(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::Return)?;
+        let expr = self.parse_expression()?;
+        self.eat(TokenType::SemiColon)?;
+
+        Ok(Stmt::Return(expr))
+    }
+
+    fn parse_throw_stmt(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::Throw)?;
+        let expr = self.parse_expression()?;
+        self.eat(TokenType::SemiColon)?;
 
-        Stmt::Block(statements)
+        Ok(Stmt::Throw(expr))
     }
 
-    fn parse_return_stmt(&mut self) -> Stmt {
-        self.eat(TokenType::Return);
-        let expr = self.parse_expression();
-        self.eat(TokenType::SemiColon);
+    fn parse_try_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let token = self.eat(TokenType::Try)?;
+        let body = self.parse_block_stmt()?;
+
+        let mut catch = None;
+        if self.peek().kind == TokenType::Catch {
+            self.eat(TokenType::Catch)?;
+            self.eat(TokenType::OpenParen)?;
+            let identifier = self.eat(TokenType::Identifier)?.value;
+            self.eat(TokenType::CloseParen)?;
+            catch = Some((identifier, Box::new(self.parse_block_stmt()?)));
+        }
+
+        let mut finally = None;
+        if self.peek().kind == TokenType::Finally {
+            self.eat(TokenType::Finally)?;
+            finally = Some(Box::new(self.parse_block_stmt()?));
+        }
+
+        if catch.is_none() && finally.is_none() {
+            return Err(ParseError::new(
+                &token,
+                "expected catch or finally after try block".to_string(),
+            ));
+        }
 
-        Stmt::Return(expr)
+        Ok(Stmt::Try(Box::new(body), catch, finally))
     }
 
-    fn parse_func_declaration(&mut self) -> Stmt {
-        self.eat(TokenType::Func);
-        let identifier = self.eat(TokenType::Identifier).value;
+    fn parse_func_declaration(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::Func)?;
+        let identifier = self.eat(TokenType::Identifier)?.value;
 
         let mut parameters: Vec<String> = vec![];
-        self.eat(TokenType::OpenParen);
+        self.eat(TokenType::OpenParen)?;
         while self.peek().kind != TokenType::CloseParen {
-            parameters.push(self.eat(TokenType::Identifier).value);
+            parameters.push(self.eat(TokenType::Identifier)?.value);
+            if self.peek().kind == TokenType::Comma {
+                self.eat(TokenType::Comma)?;
+            }
+        }
+        self.eat(TokenType::CloseParen)?;
+
+        let block = self.parse_block_stmt()?;
+
+        Ok(Stmt::FuncDeclaration(
+            identifier,
+            parameters,
+            Box::new(block),
+        ))
+    }
+
+    fn parse_struct_declaration(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::Struct)?;
+        let identifier = self.eat(TokenType::Identifier)?.value;
+
+        let mut fields: Vec<(String, Type)> = vec![];
+        self.eat(TokenType::OpenBrace)?;
+        while self.peek().kind != TokenType::CloseBrace {
+            let field = self.eat(TokenType::Identifier)?.value;
+            self.eat(TokenType::Colon)?;
+            fields.push((field, self.parse_type()?));
+            if self.peek().kind == TokenType::Comma {
+                self.eat(TokenType::Comma)?;
+            }
+        }
+        self.eat(TokenType::CloseBrace)?;
+
+        Ok(Stmt::StructDeclaration(identifier, fields))
+    }
+
+    fn parse_enum_declaration(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::Enum)?;
+        let identifier = self.eat(TokenType::Identifier)?.value;
+
+        let mut variants: Vec<(String, Vec<Type>)> = vec![];
+        self.eat(TokenType::OpenBrace)?;
+        while self.peek().kind != TokenType::CloseBrace {
+            let variant = self.eat(TokenType::Identifier)?.value;
+            let mut fields: Vec<Type> = vec![];
+            if self.peek().kind == TokenType::OpenParen {
+                self.eat(TokenType::OpenParen)?;
+                while self.peek().kind != TokenType::CloseParen {
+                    fields.push(self.parse_type()?);
+                    if self.peek().kind == TokenType::Comma {
+                        self.eat(TokenType::Comma)?;
+                    }
+                }
+                self.eat(TokenType::CloseParen)?;
+            }
+            variants.push((variant, fields));
             if self.peek().kind == TokenType::Comma {
-                self.eat(TokenType::Comma);
+                self.eat(TokenType::Comma)?;
             }
         }
-        self.eat(TokenType::CloseParen);
+        self.eat(TokenType::CloseBrace)?;
+
+        Ok(Stmt::EnumDeclaration(identifier, variants))
+    }
+
+    fn parse_impl_declaration(&mut self) -> Result<Stmt, ParseError> {
+        self.eat(TokenType::Impl)?;
+        let identifier = self.eat(TokenType::Identifier)?.value;
+
+        let mut methods: Vec<Stmt> = vec![];
+        self.eat(TokenType::OpenBrace)?;
+        while self.peek().kind != TokenType::CloseBrace {
+            methods.push(self.parse_func_declaration()?);
+        }
+        self.eat(TokenType::CloseBrace)?;
+
+        Ok(Stmt::ImplDeclaration(identifier, methods))
+    }
+
+    fn parse_var_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let is_const = match self.peek().kind {
+            TokenType::Let => false,
+            TokenType::Const => true,
+            _ => return Err(self.unexpected()),
+        };
+        self.advance();
+
+        let identifier = self.eat(TokenType::Identifier)?;
 
-        let block = self.parse_block_stmt();
+        self.eat(TokenType::Colon)?;
+        let typing = self.parse_type()?;
 
-        Stmt::FuncDeclaration(identifier, parameters, Box::new(block))
+        self.eat(TokenType::Equal)?;
+        let expr = self.parse_expression()?;
+
+        self.eat(TokenType::SemiColon)?;
+
+        Ok(Stmt::VarDeclaration(
+            identifier.value,
+            typing,
+            is_const,
+            expr,
+        ))
     }
 
-    fn parse_var_declaration(&mut self) -> Stmt {
-        let is_const: bool;
-        let var_type = self.eat(self.peek().kind);
-        match var_type.kind {
-            TokenType::Let => is_const = false,
-            TokenType::Const => is_const = true,
-            _ => panic!("Expected let or const, got {:?}", var_type.kind),
+    fn parse_type(&mut self) -> Result<Type, ParseError> {
+        let token = self.advance();
+        let typing = match token.kind {
+            TokenType::IntType => Type::Int,
+            TokenType::BigIntType => Type::BigInt,
+            TokenType::FloatType => Type::Float,
+            TokenType::StringType => Type::String,
+            TokenType::BoolType => Type::Bool,
+            TokenType::ListType => {
+                self.eat(TokenType::Less)?;
+                let element = self.nested(Self::parse_type)?;
+                self.eat_closing_angle()?;
+
+                Type::List(Box::new(element))
+            }
+            TokenType::MapType => {
+                self.eat(TokenType::Less)?;
+                let key = self.nested(Self::parse_type)?;
+                self.eat(TokenType::Comma)?;
+                let value = self.nested(Self::parse_type)?;
+                self.eat_closing_angle()?;
+
+                Type::Map(Box::new(key), Box::new(value))
+            }
+            TokenType::Identifier => Type::Named(token.value),
+            TokenType::Invalid => return Err(ParseError::unexpected(&token)),
+            _ => {
+                return Err(ParseError::new(
+                    &token,
+                    format!("invalid variable type {:?}", token.kind),
+                ))
+            }
         };
 
-        let identifier = self.eat(TokenType::Identifier);
+        Ok(typing)
+    }
+
+    /// Eat the `>` closing a generic type, a `>>` closing two nested types at
+    /// once (`list<list<int>>`) is split in two.
+    fn eat_closing_angle(&mut self) -> Result<(), ParseError> {
+        if let Some(token) = self.tokens.last_mut() {
+            if token.kind == TokenType::GreaterGreater {
+                token.kind = TokenType::Greater;
+                token.value = ">".to_string();
+                token.column += 1;
+                return Ok(());
+            }
+        }
+
+        self.eat(TokenType::Greater)?;
+        Ok(())
+    }
+
+    /// Operators from the lowest to the highest precedence:
+    ///
+    /// - assignments `=`, `+=`, `-=`, `*=`, `/=`, `%=` (right associative)
+    /// - comparisons `==`, `!=`, `<`, `<=`, `>`, `>=`
+    /// - `|`, then `^`, then `&`
+    /// - shifts `<<`, `>>`
+    /// - `+`, `-`
+    /// - `*`, `/`, `%`
+    /// - `**` (right associative)
+    /// - calls, indexes and member accesses
+    fn parse_assignment_expr(&mut self) -> Result<Expr, ParseError> {
+        let left = self.parse_comparison_expr()?;
 
-        self.eat(TokenType::Colon);
-        let typing = match self.eat(self.peek().kind).kind {
-            TokenType::IntType => TokenType::Integer,
-            TokenType::FloatType => TokenType::Float,
-            TokenType::StringType => TokenType::String,
-            TokenType::BoolType => TokenType::Bool,
-            _ => panic!("Invalid variable type"),
+        // The target of `a op= b` is kept once in the tree, so an index or
+        // receiver with side effects is only evaluated once
+        let op = match self.peek().kind {
+            TokenType::Equal
+            | TokenType::PlusEqual
+            | TokenType::MinusEqual
+            | TokenType::StarEqual
+            | TokenType::SlashEqual
+            | TokenType::PercentEqual => self.advance().kind,
+            _ => return Ok(left),
         };
+        let right = self.nested(Self::parse_assignment_expr)?;
+        self.eat(TokenType::SemiColon)?;
+
+        Ok(Expr::Assignment(Box::new(left), op, Box::new(right)))
+    }
+
+    fn parse_comparison_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_bitwise_or_expr()?;
+
+        while matches!(
+            self.peek().kind,
+            TokenType::EqualEqual
+                | TokenType::BangEqual
+                | TokenType::Less
+                | TokenType::LessEqual
+                | TokenType::Greater
+                | TokenType::GreaterEqual
+        ) {
+            self.nest()?;
+            let op = self.advance();
+            let right = self.parse_bitwise_or_expr()?;
+
+            expr = Expr::Binary(Box::new(expr), op.kind, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_bitwise_or_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_bitwise_xor_expr()?;
+
+        while self.peek().kind == TokenType::Pipe {
+            self.nest()?;
+            let op = self.advance();
+            let right = self.parse_bitwise_xor_expr()?;
+
+            expr = Expr::Binary(Box::new(expr), op.kind, Box::new(right));
+        }
+
+        Ok(expr)
+    }
 
-        self.eat(TokenType::Equal);
-        let expr = self.parse_expression();
+    fn parse_bitwise_xor_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_bitwise_and_expr()?;
 
-        self.eat(TokenType::SemiColon);
+        while self.peek().kind == TokenType::Caret {
+            self.nest()?;
+            let op = self.advance();
+            let right = self.parse_bitwise_and_expr()?;
+
+            expr = Expr::Binary(Box::new(expr), op.kind, Box::new(right));
+        }
+
+        Ok(expr)
+    }
+
+    fn parse_bitwise_and_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_shift_expr()?;
+
+        while self.peek().kind == TokenType::Ampersand {
+            self.nest()?;
+            let op = self.advance();
+            let right = self.parse_shift_expr()?;
+
+            expr = Expr::Binary(Box::new(expr), op.kind, Box::new(right));
+        }
 
-        Stmt::VarDeclaration(identifier.value, typing, is_const, expr)
+        Ok(expr)
     }
 
-    fn parse_assignment_expr(&mut self) -> Expr {
-        let left = self.parse_additive_expr();
+    fn parse_shift_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_additive_expr()?;
 
-        if self.peek().kind == TokenType::Equal {
+        while matches!(
+            self.peek().kind,
+            TokenType::LessLess | TokenType::GreaterGreater
+        ) {
+            self.nest()?;
             let op = self.advance();
-            let right = self.parse_assignment_expr();
-            self.eat(TokenType::SemiColon);
+            let right = self.parse_additive_expr()?;
 
-            return Expr::Assignment(Box::new(left), op.kind, Box::new(right));
+            expr = Expr::Binary(Box::new(expr), op.kind, Box::new(right));
         }
 
-        left
+        Ok(expr)
     }
 
-    fn parse_additive_expr(&mut self) -> Expr {
-        let mut expr = self.parse_multiplicative_expr();
+    fn parse_additive_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_multiplicative_expr()?;
 
         while self.peek().kind == TokenType::Plus || self.peek().kind == TokenType::Minus {
+            self.nest()?;
             let op = self.advance();
-            let right = self.parse_multiplicative_expr();
+            let right = self.parse_multiplicative_expr()?;
 
             expr = Expr::Binary(Box::new(expr), op.kind, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_multiplicative_expr(&mut self) -> Expr {
-        let mut expr = self.parse_func_call_expr();
+    fn parse_multiplicative_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_power_expr()?;
 
-        while self.peek().kind == TokenType::Star || self.peek().kind == TokenType::Slash {
+        while matches!(
+            self.peek().kind,
+            TokenType::Star | TokenType::Slash | TokenType::Percent
+        ) {
+            self.nest()?;
             let op = self.advance();
-            let right = self.parse_func_call_expr();
+            let right = self.parse_power_expr()?;
 
             expr = Expr::Binary(Box::new(expr), op.kind, Box::new(right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_func_call_expr(&mut self) -> Expr {
-        let primary = self.parse_primary_expr();
-        match &primary {
-            Expr::Identifier(name) => {
-                if self.peek().kind != TokenType::OpenParen {
-                    return primary;
+    fn parse_power_expr(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_func_call_expr()?;
+
+        // Right associative, `2 ** 3 ** 2` is `2 ** (3 ** 2)`
+        if self.peek().kind == TokenType::StarStar {
+            let op = self.advance();
+            let right = self.nested(Self::parse_power_expr)?;
+
+            return Ok(Expr::Binary(Box::new(expr), op.kind, Box::new(right)));
+        }
+
+        Ok(expr)
+    }
+
+    /// Parse a primary expression followed by any chain of calls, indexes,
+    /// field accesses and method calls, e.g. `a.b(1)[0].c`.
+    fn parse_func_call_expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_primary_expr()?;
+
+        loop {
+            if matches!(
+                self.peek().kind,
+                TokenType::OpenBracket | TokenType::Dot | TokenType::Question
+            ) {
+                self.nest()?;
+            }
+
+            match self.peek().kind {
+                TokenType::OpenParen => {
+                    let name = match &expr {
+                        Expr::Identifier(name) => name.clone(),
+                        _ => {
+                            return Err(ParseError::new(
+                                &self.peek(),
+                                "only named functions can be called".to_string(),
+                            ))
+                        }
+                    };
+
+                    expr = Expr::CallExpr(name, self.parse_call_args()?);
                 }
+                TokenType::OpenBracket => {
+                    self.eat(TokenType::OpenBracket)?;
+                    let index = self.parse_expression()?;
+                    self.eat(TokenType::CloseBracket)?;
 
-                self.eat(TokenType::OpenParen);
-                let mut args: Vec<Expr> = vec![];
-                while self.peek().kind != TokenType::CloseParen {
-                    args.push(self.parse_expression());
-                    if self.peek().kind == TokenType::Comma {
-                        self.eat(TokenType::Comma);
-                    }
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
                 }
-                self.eat(TokenType::CloseParen);
+                TokenType::Dot => {
+                    self.eat(TokenType::Dot)?;
+                    let member = self.eat(TokenType::Identifier)?.value;
+
+                    expr = match self.peek().kind {
+                        TokenType::OpenParen => {
+                            Expr::MethodCall(Box::new(expr), member, self.parse_call_args()?)
+                        }
+                        _ => Expr::Member(Box::new(expr), member),
+                    };
+                }
+                TokenType::Question => {
+                    self.eat(TokenType::Question)?;
+                    expr = Expr::Try(Box::new(expr));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
 
-                Expr::CallExpr(name.clone(), args)
+    fn parse_match_expr(&mut self) -> Result<Expr, ParseError> {
+        self.eat(TokenType::Match)?;
+        let subject = self.parse_condition()?;
+
+        let mut arms: Vec<(Pattern, Expr)> = vec![];
+        self.eat(TokenType::OpenBrace)?;
+        while self.peek().kind != TokenType::CloseBrace {
+            let pattern = self.parse_pattern()?;
+            self.eat(TokenType::FatArrow)?;
+            arms.push((pattern, self.parse_expression()?));
+            if self.peek().kind == TokenType::Comma {
+                self.eat(TokenType::Comma)?;
             }
-            _ => primary,
         }
+        self.eat(TokenType::CloseBrace)?;
+
+        Ok(Expr::Match(Box::new(subject), arms))
     }
 
-    fn parse_primary_expr(&mut self) -> Expr {
+    fn parse_pattern(&mut self) -> Result<Pattern, ParseError> {
         match self.peek().kind {
-            TokenType::Integer => Expr::Literal(Atom::Integer(
-                self.eat(TokenType::Integer)
-                    .value
-                    .parse()
-                    .expect("Parser error: expected integer"),
-            )),
-            TokenType::Float => Expr::Literal(Atom::Float(
-                self.eat(TokenType::Float)
-                    .value
-                    .parse()
-                    .expect("Parser error: expected float"),
-            )),
-            TokenType::String => Expr::Literal(Atom::String(self.eat(TokenType::String).value)),
-            TokenType::Bool => Expr::Literal(Atom::Bool(self.eat(TokenType::Bool).value == "true")),
+            TokenType::Integer
+            | TokenType::BigInt
+            | TokenType::Float
+            | TokenType::String
+            | TokenType::Bool => match self.parse_primary_expr()? {
+                Expr::Literal(atom) => Ok(Pattern::Literal(atom)),
+                _ => Err(self.unexpected()),
+            },
+            TokenType::Identifier => {
+                let identifier = self.eat(TokenType::Identifier)?.value;
+                let (identifier, variant) = match self.parse_variant_name(&identifier)? {
+                    Some(names) => names,
+                    None if identifier == "_" => return Ok(Pattern::Wildcard),
+                    None => return Ok(Pattern::Binding(identifier)),
+                };
+
+                let mut fields: Vec<Pattern> = vec![];
+                if self.peek().kind == TokenType::OpenParen {
+                    self.eat(TokenType::OpenParen)?;
+                    while self.peek().kind != TokenType::CloseParen {
+                        fields.push(self.nested(Self::parse_pattern)?);
+                        if self.peek().kind == TokenType::Comma {
+                            self.eat(TokenType::Comma)?;
+                        }
+                    }
+                    self.eat(TokenType::CloseParen)?;
+                }
+
+                Ok(Pattern::Variant(identifier, variant, fields))
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Resolve the enum and variant named by `Enum::Variant`, or by a bare
+    /// variant of the built-in `Option` and `Result` enums (`Some`, `None`,
+    /// `Ok`, `Err`). `None` for any other identifier.
+    fn parse_variant_name(
+        &mut self,
+        identifier: &str,
+    ) -> Result<Option<(String, String)>, ParseError> {
+        if self.peek().kind == TokenType::DoubleColon {
+            self.eat(TokenType::DoubleColon)?;
+            let variant = self.eat(TokenType::Identifier)?.value;
+            return Ok(Some((identifier.to_string(), variant)));
+        }
+
+        let names = match identifier {
+            "Some" | "None" => Some(("Option".to_string(), identifier.to_string())),
+            "Ok" | "Err" => Some(("Result".to_string(), identifier.to_string())),
+            _ => None,
+        };
+
+        Ok(names)
+    }
+
+    /// Eat the `,` following an element, it can only be left out after the
+    /// last one, e.g. `[1, 2,]` and `[1, 2]` but not `[1 2]`.
+    fn eat_separator(&mut self, closing: TokenType) -> Result<(), ParseError> {
+        if self.peek().kind != closing {
+            self.eat(TokenType::Comma)?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_call_args(&mut self) -> Result<Vec<Expr>, ParseError> {
+        self.eat(TokenType::OpenParen)?;
+        let mut args: Vec<Expr> = vec![];
+        while self.peek().kind != TokenType::CloseParen {
+            args.push(self.parse_expression()?);
+            self.eat_separator(TokenType::CloseParen)?;
+        }
+        self.eat(TokenType::CloseParen)?;
+
+        Ok(args)
+    }
+
+    /// Parse the value of a literal token, e.g. an integer too large for an
+    /// `int`.
+    fn parse_literal<T: std::str::FromStr>(
+        &mut self,
+        token_type: TokenType,
+    ) -> Result<T, ParseError> {
+        let token = self.eat(token_type)?;
+        token.value.parse().map_err(|_| {
+            ParseError::new(
+                &token,
+                format!("invalid {:?} literal {}", token.kind, token.value),
+            )
+        })
+    }
+
+    fn parse_primary_expr(&mut self) -> Result<Expr, ParseError> {
+        let expr = match self.peek().kind {
+            TokenType::Integer => {
+                Expr::Literal(Atom::Integer(self.parse_literal(TokenType::Integer)?))
+            }
+            TokenType::BigInt => {
+                Expr::Literal(Atom::BigInt(self.parse_literal(TokenType::BigInt)?))
+            }
+            TokenType::Float => Expr::Literal(Atom::Float(self.parse_literal(TokenType::Float)?)),
+            TokenType::String => Expr::Literal(Atom::String(self.eat(TokenType::String)?.value)),
+            TokenType::Bool => {
+                Expr::Literal(Atom::Bool(self.eat(TokenType::Bool)?.value == "true"))
+            }
             TokenType::OpenParen => {
-                self.eat(TokenType::OpenParen);
+                self.eat(TokenType::OpenParen)?;
+                let no_struct_literal = self.no_struct_literal;
+                self.no_struct_literal = false;
                 let expr = self.parse_expression();
-                self.eat(TokenType::CloseParen);
+                self.no_struct_literal = no_struct_literal;
+                let expr = expr?;
+                self.eat(TokenType::CloseParen)?;
                 expr
             }
-            TokenType::Identifier => Expr::Identifier(self.eat(TokenType::Identifier).value),
-            _ => panic!("Parser error: unexpected token: {:?}", self.peek().kind),
-        }
+            TokenType::OpenBracket => {
+                self.eat(TokenType::OpenBracket)?;
+                let mut elements: Vec<Expr> = vec![];
+                while self.peek().kind != TokenType::CloseBracket {
+                    elements.push(self.parse_expression()?);
+                    self.eat_separator(TokenType::CloseBracket)?;
+                }
+                self.eat(TokenType::CloseBracket)?;
+
+                Expr::List(elements)
+            }
+            TokenType::OpenBrace => {
+                self.eat(TokenType::OpenBrace)?;
+                let mut entries: Vec<(Expr, Expr)> = vec![];
+                while self.peek().kind != TokenType::CloseBrace {
+                    let key = self.parse_expression()?;
+                    self.eat(TokenType::Colon)?;
+                    let value = self.parse_expression()?;
+                    entries.push((key, value));
+                    if self.peek().kind == TokenType::Comma {
+                        self.eat(TokenType::Comma)?;
+                    }
+                }
+                self.eat(TokenType::CloseBrace)?;
+
+                Expr::Map(entries)
+            }
+            TokenType::Match => self.parse_match_expr()?,
+            // Conversion functions are named after the type they convert to
+            TokenType::IntType
+            | TokenType::FloatType
+            | TokenType::StringType
+            | TokenType::BoolType
+                if self.peek_nth(1) == TokenType::OpenParen =>
+            {
+                Expr::Identifier(self.advance().value)
+            }
+            TokenType::Identifier => {
+                let identifier = self.eat(TokenType::Identifier)?.value;
+                if let Some((identifier, variant)) = self.parse_variant_name(&identifier)? {
+                    let args = match self.peek().kind {
+                        TokenType::OpenParen => self.parse_call_args()?,
+                        _ => vec![],
+                    };
+
+                    return Ok(Expr::Variant(identifier, variant, args));
+                }
+
+                if self.no_struct_literal || self.peek().kind != TokenType::OpenBrace {
+                    return Ok(Expr::Identifier(identifier));
+                }
+
+                self.eat(TokenType::OpenBrace)?;
+                let mut fields: Vec<(String, Expr)> = vec![];
+                while self.peek().kind != TokenType::CloseBrace {
+                    let field = self.eat(TokenType::Identifier)?.value;
+                    self.eat(TokenType::Colon)?;
+                    fields.push((field, self.parse_expression()?));
+                    if self.peek().kind == TokenType::Comma {
+                        self.eat(TokenType::Comma)?;
+                    }
+                }
+                self.eat(TokenType::CloseBrace)?;
+
+                Expr::StructLiteral(identifier, fields)
+            }
+            _ => return Err(self.unexpected()),
+        };
+
+        Ok(expr)
     }
 }
 
@@ -267,13 +902,13 @@ mod test {
     #[test]
     fn test_let_var_declaration() {
         let mut parser = Parser::new("let x: int = 1;");
-        let ast = parser.parse();
+        let ast = parser.parse().unwrap();
 
         assert_eq!(
             ast.statements[0],
             Stmt::VarDeclaration(
                 "x".to_string(),
-                TokenType::Integer,
+                Type::Int,
                 false,
                 Expr::Literal(Atom::Integer(1))
             )
@@ -283,16 +918,449 @@ mod test {
     #[test]
     fn test_const_var_declaration() {
         let mut parser = Parser::new("const x: float = 1.1;");
-        let ast = parser.parse();
+        let ast = parser.parse().unwrap();
 
         assert_eq!(
             ast.statements[0],
             Stmt::VarDeclaration(
                 "x".to_string(),
-                TokenType::Float,
+                Type::Float,
                 true,
                 Expr::Literal(Atom::Float(1.1))
             )
         );
     }
+
+    #[test]
+    fn test_if_else_stmt() {
+        let mut parser = Parser::new("if x >= 1 { print x; } else { print 0; }");
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::If(
+                Expr::Binary(
+                    Box::new(Expr::Identifier("x".to_string())),
+                    TokenType::GreaterEqual,
+                    Box::new(Expr::Literal(Atom::Integer(1)))
+                ),
+                Box::new(Stmt::Block(vec![Stmt::Print(Expr::Identifier(
+                    "x".to_string()
+                ))])),
+                Some(Box::new(Stmt::Block(vec![Stmt::Print(Expr::Literal(
+                    Atom::Integer(0)
+                ))])))
+            )
+        );
+    }
+
+    #[test]
+    fn test_list_declaration_and_index() {
+        let mut parser = Parser::new("let xs: list<int> = [1, 2]; xs[0] = xs[1];");
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::VarDeclaration(
+                "xs".to_string(),
+                Type::List(Box::new(Type::Int)),
+                false,
+                Expr::List(vec![
+                    Expr::Literal(Atom::Integer(1)),
+                    Expr::Literal(Atom::Integer(2))
+                ])
+            )
+        );
+        assert_eq!(
+            ast.statements[1],
+            Stmt::Expression(Expr::Assignment(
+                Box::new(Expr::Index(
+                    Box::new(Expr::Identifier("xs".to_string())),
+                    Box::new(Expr::Literal(Atom::Integer(0)))
+                )),
+                TokenType::Equal,
+                Box::new(Expr::Index(
+                    Box::new(Expr::Identifier("xs".to_string())),
+                    Box::new(Expr::Literal(Atom::Integer(1)))
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_missing_comma() {
+        let err = Parser::new("print [1 2 3];").parse().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected Comma, got Integer at line 1 and column 10"
+        );
+
+        let err = Parser::new("f(1 2);").parse().unwrap_err();
+        assert_eq!((err.line, err.column), (1, 5));
+
+        // A trailing comma is still accepted
+        let ast = Parser::new("[1, 2,];").parse().unwrap();
+        assert_eq!(
+            ast.statements[0],
+            Stmt::Expression(Expr::List(vec![
+                Expr::Literal(Atom::Integer(1)),
+                Expr::Literal(Atom::Integer(2))
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_map_literal_and_block() {
+        let mut parser = Parser::new(
+            "
+        let m: map<string, int> = { 'a': 1 };
+        { 'b': 2 }
+        { print m; }
+        ",
+        );
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::VarDeclaration(
+                "m".to_string(),
+                Type::Map(Box::new(Type::String), Box::new(Type::Int)),
+                false,
+                Expr::Map(vec![(
+                    Expr::Literal(Atom::String("a".to_string())),
+                    Expr::Literal(Atom::Integer(1))
+                )])
+            )
+        );
+        assert_eq!(
+            ast.statements[1],
+            Stmt::Expression(Expr::Map(vec![(
+                Expr::Literal(Atom::String("b".to_string())),
+                Expr::Literal(Atom::Integer(2))
+            )]))
+        );
+        assert_eq!(
+            ast.statements[2],
+            Stmt::Block(vec![Stmt::Print(Expr::Identifier("m".to_string()))])
+        );
+    }
+
+    #[test]
+    fn test_struct_declaration_and_literal() {
+        let mut parser = Parser::new(
+            "
+        struct Point { x: float, y: float }
+        let p: Point = Point { x: 1.0, y: 2.0 };
+        if p.x == 1.0 { p.y = 3.0; }
+        ",
+        );
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::StructDeclaration(
+                "Point".to_string(),
+                vec![
+                    ("x".to_string(), Type::Float),
+                    ("y".to_string(), Type::Float)
+                ]
+            )
+        );
+        assert_eq!(
+            ast.statements[1],
+            Stmt::VarDeclaration(
+                "p".to_string(),
+                Type::Named("Point".to_string()),
+                false,
+                Expr::StructLiteral(
+                    "Point".to_string(),
+                    vec![
+                        ("x".to_string(), Expr::Literal(Atom::Float(1.0))),
+                        ("y".to_string(), Expr::Literal(Atom::Float(2.0)))
+                    ]
+                )
+            )
+        );
+        assert_eq!(
+            ast.statements[2],
+            Stmt::If(
+                Expr::Binary(
+                    Box::new(Expr::Member(
+                        Box::new(Expr::Identifier("p".to_string())),
+                        "x".to_string()
+                    )),
+                    TokenType::EqualEqual,
+                    Box::new(Expr::Literal(Atom::Float(1.0)))
+                ),
+                Box::new(Stmt::Block(vec![Stmt::Expression(Expr::Assignment(
+                    Box::new(Expr::Member(
+                        Box::new(Expr::Identifier("p".to_string())),
+                        "y".to_string()
+                    )),
+                    TokenType::Equal,
+                    Box::new(Expr::Literal(Atom::Float(3.0)))
+                ))])),
+                None
+            )
+        );
+    }
+
+    #[test]
+    fn test_impl_and_method_call_chain() {
+        let mut parser = Parser::new(
+            "
+        impl Point {
+            func len(self) {
+                return self.x;
+            }
+        }
+        points[0].scale(2).len()
+        ",
+        );
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::ImplDeclaration(
+                "Point".to_string(),
+                vec![Stmt::FuncDeclaration(
+                    "len".to_string(),
+                    vec!["self".to_string()],
+                    Box::new(Stmt::Block(vec![Stmt::Return(Expr::Member(
+                        Box::new(Expr::Identifier("self".to_string())),
+                        "x".to_string()
+                    ))]))
+                )]
+            )
+        );
+        assert_eq!(
+            ast.statements[1],
+            Stmt::Expression(Expr::MethodCall(
+                Box::new(Expr::MethodCall(
+                    Box::new(Expr::Index(
+                        Box::new(Expr::Identifier("points".to_string())),
+                        Box::new(Expr::Literal(Atom::Integer(0)))
+                    )),
+                    "scale".to_string(),
+                    vec![Expr::Literal(Atom::Integer(2))]
+                )),
+                "len".to_string(),
+                vec![]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_enum_and_match() {
+        let mut parser = Parser::new(
+            "
+        enum Shape { Circle(float), Rect(float, float), Empty }
+        match Shape::Circle(1.0) {
+            Shape::Rect(w, _) => w,
+            0 => 1,
+            other => other,
+        }
+        ",
+        );
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::EnumDeclaration(
+                "Shape".to_string(),
+                vec![
+                    ("Circle".to_string(), vec![Type::Float]),
+                    ("Rect".to_string(), vec![Type::Float, Type::Float]),
+                    ("Empty".to_string(), vec![])
+                ]
+            )
+        );
+        assert_eq!(
+            ast.statements[1],
+            Stmt::Expression(Expr::Match(
+                Box::new(Expr::Variant(
+                    "Shape".to_string(),
+                    "Circle".to_string(),
+                    vec![Expr::Literal(Atom::Float(1.0))]
+                )),
+                vec![
+                    (
+                        Pattern::Variant(
+                            "Shape".to_string(),
+                            "Rect".to_string(),
+                            vec![Pattern::Binding("w".to_string()), Pattern::Wildcard]
+                        ),
+                        Expr::Identifier("w".to_string())
+                    ),
+                    (
+                        Pattern::Literal(Atom::Integer(0)),
+                        Expr::Literal(Atom::Integer(1))
+                    ),
+                    (
+                        Pattern::Binding("other".to_string()),
+                        Expr::Identifier("other".to_string())
+                    )
+                ]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_prelude_variants_and_try() {
+        let mut parser = Parser::new("Some(parse(text)?)");
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::Expression(Expr::Variant(
+                "Option".to_string(),
+                "Some".to_string(),
+                vec![Expr::Try(Box::new(Expr::CallExpr(
+                    "parse".to_string(),
+                    vec![Expr::Identifier("text".to_string())]
+                )))]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_try_catch_finally() {
+        let mut parser = Parser::new(
+            "
+        try {
+            throw 'error';
+        } catch (e) {
+            print e;
+        } finally {
+            print 'done';
+        }
+        ",
+        );
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::Try(
+                Box::new(Stmt::Block(vec![Stmt::Throw(Expr::Literal(Atom::String(
+                    "error".to_string()
+                )))])),
+                Some((
+                    "e".to_string(),
+                    Box::new(Stmt::Block(vec![Stmt::Print(Expr::Identifier(
+                        "e".to_string()
+                    ))]))
+                )),
+                Some(Box::new(Stmt::Block(vec![Stmt::Print(Expr::Literal(
+                    Atom::String("done".to_string())
+                ))])))
+            )
+        );
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        let mut parser = Parser::new("a | b & c << 1 + 2 * 3 ** 2");
+        let ast = parser.parse().unwrap();
+
+        let identifier = |name: &str| Box::new(Expr::Identifier(name.to_string()));
+        let integer = |value| Box::new(Expr::Literal(Atom::Integer(value)));
+        assert_eq!(
+            ast.statements[0],
+            Stmt::Expression(Expr::Binary(
+                identifier("a"),
+                TokenType::Pipe,
+                Box::new(Expr::Binary(
+                    identifier("b"),
+                    TokenType::Ampersand,
+                    Box::new(Expr::Binary(
+                        identifier("c"),
+                        TokenType::LessLess,
+                        Box::new(Expr::Binary(
+                            integer(1),
+                            TokenType::Plus,
+                            Box::new(Expr::Binary(
+                                integer(2),
+                                TokenType::Star,
+                                Box::new(Expr::Binary(integer(3), TokenType::StarStar, integer(2)))
+                            ))
+                        ))
+                    ))
+                ))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_compound_assignment_and_nested_types() {
+        let mut parser = Parser::new(
+            "
+        let grid: list<list<int>> = [];
+        x += 1;
+        ",
+        );
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::VarDeclaration(
+                "grid".to_string(),
+                Type::List(Box::new(Type::List(Box::new(Type::Int)))),
+                false,
+                Expr::List(vec![])
+            )
+        );
+        assert_eq!(
+            ast.statements[1],
+            Stmt::Expression(Expr::Assignment(
+                Box::new(Expr::Identifier("x".to_string())),
+                TokenType::PlusEqual,
+                Box::new(Expr::Literal(Atom::Integer(1)))
+            ))
+        );
+    }
+
+    #[test]
+    fn test_conversion_call() {
+        let mut parser = Parser::new("let x: int = int(\"1\");");
+        let ast = parser.parse().unwrap();
+
+        assert_eq!(
+            ast.statements[0],
+            Stmt::VarDeclaration(
+                "x".to_string(),
+                Type::Int,
+                false,
+                Expr::CallExpr(
+                    "int".to_string(),
+                    vec![Expr::Literal(Atom::String("1".to_string()))]
+                )
+            )
+        );
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let source = format!("print {}1{};", "(".repeat(200), ")".repeat(200));
+        assert!(Parser::new(&source).parse().is_ok());
+
+        let source = format!("print {}1{};", "(".repeat(5000), ")".repeat(5000));
+        let error = Parser::new(&source)
+            .parse()
+            .expect_err("Failed to get error");
+        assert_eq!(error.message, "nesting deeper than 256 levels");
+
+        // Operator chains and blocks nest as deep as parentheses
+        let source = format!("print 1{};", " + 1".repeat(1000));
+        assert!(Parser::new(&source).parse().is_err());
+
+        let source = format!("{}{}", "{".repeat(1000), "}".repeat(1000));
+        assert!(Parser::new(&source).parse().is_err());
+
+        let source = format!(
+            "let x: {}int{} = [];",
+            "list<".repeat(1000),
+            ">".repeat(1000)
+        );
+        assert!(Parser::new(&source).parse().is_err());
+    }
 }
@@ -0,0 +1,49 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    convert_fns,
+    environment::Environment,
+    json, math, patterns, strings,
+    values::{RuntimeError, RuntimeVal},
+};
+
+/// Register the native functions available to every script.
+pub fn register(env: &mut Environment) {
+    env.register_native("len", 1, len);
+    env.register_native("now", 0, now);
+    env.register_fn("wrapping_add", |left: i64, right: i64| {
+        Ok(left.wrapping_add(right))
+    });
+    env.register_fn("saturating_add", |left: i64, right: i64| {
+        Ok(left.saturating_add(right))
+    });
+    env.register_fn("is_nan", |val: f64| Ok(val.is_nan()));
+    env.register_fn("is_finite", |val: f64| Ok(val.is_finite()));
+
+    convert_fns::register(env);
+    math::register(env);
+    strings::register(env);
+    patterns::register(env);
+    json::register(env);
+}
+
+/// Number of elements of a list or map, or of characters of a string.
+fn len(args: &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> {
+    let len = match &args[0] {
+        RuntimeVal::List(elements) => elements.borrow().len(),
+        RuntimeVal::Map(map) => map.borrow().len(),
+        RuntimeVal::String(string) => string.chars().count(),
+        _ => return Err(RuntimeError::InvalidOperandType),
+    };
+
+    Ok(RuntimeVal::Int(len as i64))
+}
+
+/// Milliseconds elapsed since the Unix epoch.
+fn now(_: &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    Ok(RuntimeVal::Int(elapsed.as_millis() as i64))
+}
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use super::{
+    map::{Map, MapKey},
+    native::NativeFn,
+    values::{RuntimeError, RuntimeVal},
+};
+
+/// Conversion of a Rust value to a script value.
+pub trait IntoRuntimeVal {
+    fn into_runtime_val(self) -> RuntimeVal;
+}
+
+/// Conversion of a script value to a Rust value, failing with
+/// `RuntimeError::TypeMismatch` when the value has another type.
+pub trait FromRuntimeVal: Sized {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError>;
+}
+
+fn mismatch(expected: &str, val: &RuntimeVal) -> RuntimeError {
+    RuntimeError::TypeMismatch {
+        expected: expected.to_string(),
+        found: val.type_name().to_string(),
+    }
+}
+
+impl IntoRuntimeVal for RuntimeVal {
+    fn into_runtime_val(self) -> RuntimeVal {
+        self
+    }
+}
+
+impl FromRuntimeVal for RuntimeVal {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        Ok(val.clone())
+    }
+}
+
+// A host function returning nothing can only be called as a statement
+impl IntoRuntimeVal for () {
+    fn into_runtime_val(self) -> RuntimeVal {
+        RuntimeVal::Undefined
+    }
+}
+
+impl IntoRuntimeVal for i64 {
+    fn into_runtime_val(self) -> RuntimeVal {
+        RuntimeVal::Int(self)
+    }
+}
+
+impl FromRuntimeVal for i64 {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        match val {
+            RuntimeVal::Int(val) => Ok(*val),
+            val => Err(mismatch("int", val)),
+        }
+    }
+}
+
+impl IntoRuntimeVal for f64 {
+    fn into_runtime_val(self) -> RuntimeVal {
+        RuntimeVal::Float(self)
+    }
+}
+
+// Ints are accepted the same way they are promoted in float arithmetic
+impl FromRuntimeVal for f64 {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        match val {
+            RuntimeVal::Float(val) => Ok(*val),
+            RuntimeVal::Int(val) => Ok(*val as f64),
+            val => Err(mismatch("float", val)),
+        }
+    }
+}
+
+impl IntoRuntimeVal for bool {
+    fn into_runtime_val(self) -> RuntimeVal {
+        RuntimeVal::Bool(self)
+    }
+}
+
+impl FromRuntimeVal for bool {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        match val {
+            RuntimeVal::Bool(val) => Ok(*val),
+            val => Err(mismatch("bool", val)),
+        }
+    }
+}
+
+impl IntoRuntimeVal for String {
+    fn into_runtime_val(self) -> RuntimeVal {
+        RuntimeVal::String(self)
+    }
+}
+
+impl IntoRuntimeVal for &str {
+    fn into_runtime_val(self) -> RuntimeVal {
+        RuntimeVal::String(self.to_string())
+    }
+}
+
+impl FromRuntimeVal for String {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        match val {
+            RuntimeVal::String(val) => Ok(val.clone()),
+            val => Err(mismatch("string", val)),
+        }
+    }
+}
+
+impl<T: IntoRuntimeVal> IntoRuntimeVal for Vec<T> {
+    fn into_runtime_val(self) -> RuntimeVal {
+        RuntimeVal::new_list(self.into_iter().map(T::into_runtime_val).collect())
+    }
+}
+
+impl<T: FromRuntimeVal> FromRuntimeVal for Vec<T> {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        match val {
+            RuntimeVal::List(elements) => {
+                elements.borrow().iter().map(T::from_runtime_val).collect()
+            }
+            val => Err(mismatch("list", val)),
+        }
+    }
+}
+
+// Keys are sorted so the order of the script map doesn't depend on hashing
+impl<T: IntoRuntimeVal> IntoRuntimeVal for HashMap<String, T> {
+    fn into_runtime_val(self) -> RuntimeVal {
+        let mut entries: Vec<(String, T)> = self.into_iter().collect();
+        entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        let mut map = Map::default();
+        for (key, val) in entries {
+            map.insert(MapKey::String(key), val.into_runtime_val());
+        }
+
+        RuntimeVal::new_map(map)
+    }
+}
+
+impl<T: FromRuntimeVal> FromRuntimeVal for HashMap<String, T> {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        let map = match val {
+            RuntimeVal::Map(map) => map.borrow(),
+            val => return Err(mismatch("map", val)),
+        };
+
+        map.iter()
+            .map(|(key, val)| match key {
+                MapKey::String(key) => Ok((key.clone(), T::from_runtime_val(val)?)),
+                key => Err(mismatch("string", &RuntimeVal::from(key))),
+            })
+            .collect()
+    }
+}
+
+impl<T: IntoRuntimeVal> IntoRuntimeVal for Option<T> {
+    fn into_runtime_val(self) -> RuntimeVal {
+        match self {
+            Some(val) => RuntimeVal::Enum(
+                "Option".to_string(),
+                "Some".to_string(),
+                vec![val.into_runtime_val()],
+            ),
+            None => RuntimeVal::Enum("Option".to_string(), "None".to_string(), vec![]),
+        }
+    }
+}
+
+impl<T: FromRuntimeVal> FromRuntimeVal for Option<T> {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        match val {
+            RuntimeVal::Enum(name, variant, fields) if name == "Option" => {
+                match (variant.as_str(), fields.as_slice()) {
+                    ("Some", [inner]) => Ok(Some(T::from_runtime_val(inner)?)),
+                    ("None", []) => Ok(None),
+                    _ => Err(mismatch("Option", val)),
+                }
+            }
+            val => Err(mismatch("Option", val)),
+        }
+    }
+}
+
+/// Tuples are lists with a fixed number of elements.
+macro_rules! impl_tuple {
+    ($len:literal, $($name:ident),+) => {
+        impl<$($name: IntoRuntimeVal),+> IntoRuntimeVal for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn into_runtime_val(self) -> RuntimeVal {
+                let ($($name,)+) = self;
+                RuntimeVal::new_list(vec![$($name.into_runtime_val()),+])
+            }
+        }
+
+        impl<$($name: FromRuntimeVal),+> FromRuntimeVal for ($($name,)+) {
+            fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+                let expected = concat!("list of length ", $len);
+                let elements = match val {
+                    RuntimeVal::List(elements) => elements.borrow(),
+                    val => return Err(mismatch(expected, val)),
+                };
+                if elements.len() != $len {
+                    return Err(RuntimeError::TypeMismatch {
+                        expected: expected.to_string(),
+                        found: format!("list of length {}", elements.len()),
+                    });
+                }
+
+                let mut elements = elements.iter();
+                Ok(($($name::from_runtime_val(elements.next().unwrap())?,)+))
+            }
+        }
+    };
+}
+
+impl_tuple!(1, A);
+impl_tuple!(2, A, B);
+impl_tuple!(3, A, B, C);
+impl_tuple!(4, A, B, C, D);
+
+/// Rust closures callable from scripts with typed arguments, `Args` is the
+/// tuple of their argument types.
+pub trait IntoNativeFn<Args> {
+    fn arity(&self) -> usize;
+    fn into_native_fn(self) -> Box<NativeFn>;
+}
+
+macro_rules! impl_native_fn {
+    ($len:literal $(, $name:ident)*) => {
+        impl<Func, Ret, $($name),*> IntoNativeFn<($($name,)*)> for Func
+        where
+            Func: Fn($($name),*) -> Result<Ret, RuntimeError> + 'static,
+            Ret: IntoRuntimeVal,
+            $($name: FromRuntimeVal,)*
+        {
+            fn arity(&self) -> usize {
+                $len
+            }
+
+            #[allow(non_snake_case, unused_variables, unused_mut)]
+            fn into_native_fn(self) -> Box<NativeFn> {
+                Box::new(move |args: &[RuntimeVal]| {
+                    let mut args = args.iter();
+                    $(let $name = $name::from_runtime_val(args.next().unwrap())?;)*
+                    Ok(self($($name),*)?.into_runtime_val())
+                })
+            }
+        }
+    };
+}
+
+impl_native_fn!(0);
+impl_native_fn!(1, A);
+impl_native_fn!(2, A, B);
+impl_native_fn!(3, A, B, C);
+impl_native_fn!(4, A, B, C, D);
+
+#[cfg(test)]
+mod test {
+    use crate::engine::{Engine, EngineError};
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let val = vec![(1, "a".to_string()), (2, "b".to_string())].into_runtime_val();
+        assert_eq!(val.to_string(), "[[1, a], [2, b]]");
+        assert_eq!(
+            Vec::<(i64, String)>::from_runtime_val(&val),
+            Ok(vec![(1, "a".to_string()), (2, "b".to_string())])
+        );
+
+        let map = HashMap::from([("b".to_string(), Some(2.5)), ("a".to_string(), None)]);
+        let val = map.clone().into_runtime_val();
+        assert_eq!(val.to_string(), "{a: Option::None, b: Option::Some(2.5)}");
+        assert_eq!(HashMap::from_runtime_val(&val), Ok(map));
+
+        assert_eq!(f64::from_runtime_val(&RuntimeVal::Int(2)), Ok(2.0));
+        assert_eq!(
+            "hi".into_runtime_val(),
+            RuntimeVal::String("hi".to_string())
+        );
+    }
+
+    #[test]
+    fn type_mismatch() {
+        assert_eq!(
+            Vec::<i64>::from_runtime_val(&vec!["a"].into_runtime_val()),
+            Err(RuntimeError::TypeMismatch {
+                expected: "int".to_string(),
+                found: "string".to_string()
+            })
+        );
+        assert_eq!(
+            <(i64, i64)>::from_runtime_val(&vec![1].into_runtime_val()),
+            Err(RuntimeError::TypeMismatch {
+                expected: "list of length 2".to_string(),
+                found: "list of length 1".to_string()
+            })
+        );
+        assert_eq!(
+            bool::from_runtime_val(&RuntimeVal::Int(1)),
+            Err(RuntimeError::TypeMismatch {
+                expected: "bool".to_string(),
+                found: "int".to_string()
+            })
+        );
+
+        // Only the two well-formed variants convert
+        for (variant, fields) in [("Some", vec![]), ("None", vec![RuntimeVal::Int(1)])] {
+            let val = RuntimeVal::Enum("Option".to_string(), variant.to_string(), fields);
+            assert_eq!(
+                Option::<i64>::from_runtime_val(&val),
+                Err(RuntimeError::TypeMismatch {
+                    expected: "Option".to_string(),
+                    found: "enum".to_string()
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn typed_native_functions() {
+        let engine = Engine::new();
+        engine.register_fn("repeat", |text: String, count: i64| {
+            Ok(text.repeat(count as usize))
+        });
+        engine.register_fn("total", |values: Vec<f64>| Ok(values.iter().sum::<f64>()));
+        engine.register_fn("first", |values: Vec<i64>| Ok(values.first().copied()));
+
+        let result = engine
+            .eval("[repeat(\"ab\", 2), total([1, 2.5]), first([]), first([3])]")
+            .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[abab, 3.5, Option::None, Option::Some(3)]"
+        );
+
+        assert!(matches!(
+            engine.eval("repeat(1, 2)"),
+            Err(EngineError::Runtime(RuntimeError::TypeMismatch { .. }))
+        ));
+        assert!(matches!(
+            engine.eval("repeat(\"a\")"),
+            Err(EngineError::Runtime(
+                RuntimeError::InvalidFuncCallParametersCount(_)
+            ))
+        ));
+    }
+}
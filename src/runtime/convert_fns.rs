@@ -0,0 +1,108 @@
+use super::{
+    environment::Environment,
+    strings,
+    values::{RuntimeError, RuntimeVal},
+};
+
+/// Register the conversions between the scalar types and `type_of`.
+///
+/// A conversion that would lose the value, e.g. `int("4x")` or `int(1e30)`,
+/// fails with `RuntimeError::InvalidConversion` instead of guessing.
+pub fn register(env: &mut Environment) {
+    env.register_native("str", 1, str);
+    env.register_native("int", 1, int);
+    env.register_native("float", 1, float);
+    env.register_native("bool", 1, bool);
+    env.register_native("type_of", 1, type_of);
+}
+
+fn invalid(val: &RuntimeVal, typing: &str) -> RuntimeError {
+    RuntimeError::InvalidConversion(format!(
+        "cannot convert a {} value to {typing}",
+        val.type_name()
+    ))
+}
+
+/// Same text as `print` gives.
+fn str(args: &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> {
+    Ok(RuntimeVal::String(args[0].to_string()))
+}
+
+/// Floats are truncated toward zero, booleans give 0 or 1.
+fn int(args: &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> {
+    let val = match &args[0] {
+        RuntimeVal::Int(val) => *val,
+        RuntimeVal::BigInt(val) => val.to_string().parse().map_err(|_| {
+            RuntimeError::InvalidConversion(format!("{val} is out of the range of int"))
+        })?,
+        // `as` would saturate out of range floats and give 0 for NaN
+        RuntimeVal::Float(val) => {
+            let truncated = val.trunc();
+            if !(i64::MIN as f64..i64::MAX as f64).contains(&truncated) {
+                return Err(RuntimeError::InvalidConversion(format!(
+                    "{val} is out of the range of int"
+                )));
+            }
+            truncated as i64
+        }
+        RuntimeVal::Bool(val) => *val as i64,
+        RuntimeVal::String(text) => text
+            .parse()
+            .map_err(|_| strings::cannot_parse(text, "int"))?,
+        val => return Err(invalid(val, "int")),
+    };
+
+    Ok(RuntimeVal::Int(val))
+}
+
+/// Bigints and strings must give a finite float, parsing alone would give
+/// `inf` for a value too large.
+fn float(args: &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> {
+    let val = match &args[0] {
+        RuntimeVal::Int(val) => *val as f64,
+        RuntimeVal::BigInt(val) => finite(val.to_string().parse()).ok_or_else(|| {
+            RuntimeError::InvalidConversion(format!("{val} is out of the range of float"))
+        })?,
+        RuntimeVal::Float(val) => *val,
+        RuntimeVal::Bool(val) => *val as i64 as f64,
+        RuntimeVal::String(text) => {
+            finite(text.parse()).ok_or_else(|| strings::cannot_parse(text, "float"))?
+        }
+        val => return Err(invalid(val, "float")),
+    };
+
+    Ok(RuntimeVal::Float(val))
+}
+
+fn finite<E>(val: Result<f64, E>) -> Option<f64> {
+    val.ok().filter(|val| val.is_finite())
+}
+
+/// Only `"true"` and `"false"` are booleans as strings, ints are true when
+/// they aren't 0.
+fn bool(args: &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> {
+    let val = match &args[0] {
+        RuntimeVal::Bool(val) => *val,
+        RuntimeVal::Int(val) => *val != 0,
+        RuntimeVal::String(text) => match text.as_str() {
+            "true" => true,
+            "false" => false,
+            _ => return Err(strings::cannot_parse(text, "bool")),
+        },
+        val => return Err(invalid(val, "bool")),
+    };
+
+    Ok(RuntimeVal::Bool(val))
+}
+
+/// Name of the type as written in declarations, structs and enums give their
+/// own name.
+fn type_of(args: &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> {
+    let name = match &args[0] {
+        RuntimeVal::Struct(instance) => instance.borrow().def.name.clone(),
+        RuntimeVal::Enum(name, ..) => name.clone(),
+        val => val.type_name().to_string(),
+    };
+
+    Ok(RuntimeVal::String(name))
+}
@@ -0,0 +1,44 @@
+use crate::ast::types::Type;
+
+use super::values::{RuntimeError, RuntimeVal};
+
+/// Declared variants of an enum, each with the types of its fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDef {
+    pub name: String,
+    pub variants: Vec<(String, Vec<Type>)>,
+}
+
+impl EnumDef {
+    /// Build a variant value, the number and types of the fields must match
+    /// the variant declaration.
+    pub fn instantiate(
+        &self,
+        variant: &str,
+        values: Vec<RuntimeVal>,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let full_name = format!("{}::{variant}", self.name);
+        let (_, types) = self
+            .variants
+            .iter()
+            .find(|(name, _)| name == variant)
+            .ok_or_else(|| RuntimeError::UndefinedVariant(full_name.clone()))?;
+
+        if types.len() != values.len() {
+            return Err(RuntimeError::InvalidFuncCallParametersCount(full_name));
+        }
+        if !values
+            .iter()
+            .zip(types)
+            .all(|(val, typing)| val.matches_type(typing))
+        {
+            return Err(RuntimeError::InvalidType);
+        }
+
+        Ok(RuntimeVal::Enum(
+            self.name.clone(),
+            variant.to_string(),
+            values,
+        ))
+    }
+}
@@ -4,7 +4,17 @@ use std::{
     rc::Rc,
 };
 
-use super::values::{RuntimeError, RuntimeVal};
+use crate::ast::types::Type;
+
+use super::{
+    builtins,
+    convert::IntoNativeFn,
+    enums::EnumDef,
+    interpreter::Interpreter,
+    native::NativeFunc,
+    structs::{StructDef, ERROR_STRUCT},
+    values::{RuntimeError, RuntimeVal},
+};
 
 pub type Env = Rc<RefCell<Environment>>;
 
@@ -13,7 +23,13 @@ pub struct Environment {
     parent: Option<Env>,
     variables: HashMap<String, RuntimeVal>,
     constants: HashSet<String>,
+    // Declared types of the variables declared with one
+    types: HashMap<String, Type>,
     functions: HashMap<String, RuntimeVal>,
+    structs: HashMap<String, Rc<StructDef>>,
+    enums: HashMap<String, Rc<EnumDef>>,
+    // type name -> method name -> function
+    methods: HashMap<String, HashMap<String, RuntimeVal>>,
 }
 
 impl Environment {
@@ -24,14 +40,18 @@ impl Environment {
             parent,
             variables: HashMap::new(),
             constants: HashSet::new(),
+            types: HashMap::new(),
             functions: HashMap::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            methods: HashMap::new(),
         };
 
         if !has_parent {
             env.setup_builtins();
         }
 
-        return env;
+        env
     }
 
     pub fn get_var(&self, name: &str) -> Result<RuntimeVal, RuntimeError> {
@@ -44,13 +64,17 @@ impl Environment {
         }
     }
 
-    pub fn get_func(&self, name: &str) -> Result<RuntimeVal, RuntimeError> {
-        match self.functions.get(name) {
-            Some(val) => Ok(val.clone()),
-            None => match &self.parent {
-                Some(parent) => parent.borrow().get_func(name),
-                None => Err(RuntimeError::UndefinedFunction(name.to_string())),
-            },
+    /// Find a function along with the environment it was declared in, which
+    /// is used as the parent scope when the function is called.
+    pub fn lookup_func(env: &Env, name: &str) -> Result<(RuntimeVal, Env), RuntimeError> {
+        let current = env.borrow();
+        if let Some(val) = current.functions.get(name) {
+            return Ok((val.clone(), Rc::clone(env)));
+        }
+
+        match &current.parent {
+            Some(parent) => Self::lookup_func(parent, name),
+            None => Err(RuntimeError::UndefinedFunction(name.to_string())),
         }
     }
 
@@ -63,6 +87,117 @@ impl Environment {
         Ok(())
     }
 
+    /// Expose a Rust function to scripts under `name`, it is called with
+    /// exactly `arity` arguments.
+    pub fn register_native(
+        &mut self,
+        name: &str,
+        arity: usize,
+        func: impl Fn(&[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> + 'static,
+    ) {
+        let native = NativeFunc::new(name, arity, func);
+        self.functions
+            .insert(name.to_string(), RuntimeVal::NativeFunc(Rc::new(native)));
+    }
+
+    /// Like `register_native` for a builtin that also depends on the
+    /// interpreter calling it.
+    pub(crate) fn register_builtin(
+        &mut self,
+        name: &str,
+        arity: usize,
+        func: impl Fn(&Interpreter, &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> + 'static,
+    ) {
+        let native = NativeFunc::builtin(name, arity, func);
+        self.functions
+            .insert(name.to_string(), RuntimeVal::NativeFunc(Rc::new(native)));
+    }
+
+    /// Expose a typed Rust closure to scripts, its arguments are converted
+    /// with `FromRuntimeVal` and its result with `IntoRuntimeVal`.
+    pub fn register_fn<Args>(&mut self, name: &str, func: impl IntoNativeFn<Args>) {
+        let arity = func.arity();
+        let func = func.into_native_fn();
+        self.register_native(name, arity, move |args| func(args));
+    }
+
+    /// Find a method of a type along with the environment its `impl` block was
+    /// evaluated in, the same way as `lookup_func`.
+    pub fn lookup_method(
+        env: &Env,
+        type_name: &str,
+        name: &str,
+    ) -> Result<(RuntimeVal, Env), RuntimeError> {
+        let current = env.borrow();
+        if let Some(val) = current
+            .methods
+            .get(type_name)
+            .and_then(|methods| methods.get(name))
+        {
+            return Ok((val.clone(), Rc::clone(env)));
+        }
+
+        match &current.parent {
+            Some(parent) => Self::lookup_method(parent, type_name, name),
+            None => Err(RuntimeError::UndefinedMethod(format!("{type_name}.{name}"))),
+        }
+    }
+
+    pub fn declare_method(
+        &mut self,
+        type_name: &str,
+        name: &str,
+        value: RuntimeVal,
+    ) -> Result<(), RuntimeError> {
+        let methods = self.methods.entry(type_name.to_string()).or_default();
+        if methods.contains_key(name) {
+            return Err(RuntimeError::FuncRedeclaration(format!(
+                "{type_name}.{name}"
+            )));
+        }
+
+        methods.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    pub fn get_struct(&self, name: &str) -> Result<Rc<StructDef>, RuntimeError> {
+        match self.structs.get(name) {
+            Some(def) => Ok(Rc::clone(def)),
+            None => match &self.parent {
+                Some(parent) => parent.borrow().get_struct(name),
+                None => Err(RuntimeError::UndefinedStruct(name.to_string())),
+            },
+        }
+    }
+
+    pub fn declare_struct(&mut self, def: StructDef) -> Result<(), RuntimeError> {
+        if self.structs.contains_key(&def.name) || def.name == ERROR_STRUCT {
+            return Err(RuntimeError::StructRedeclaration(def.name));
+        }
+
+        self.structs.insert(def.name.clone(), Rc::new(def));
+        Ok(())
+    }
+
+    pub fn get_enum(&self, name: &str) -> Result<Rc<EnumDef>, RuntimeError> {
+        match self.enums.get(name) {
+            Some(def) => Ok(Rc::clone(def)),
+            None => match &self.parent {
+                Some(parent) => parent.borrow().get_enum(name),
+                None => Err(RuntimeError::UndefinedEnum(name.to_string())),
+            },
+        }
+    }
+
+    pub fn declare_enum(&mut self, def: EnumDef) -> Result<(), RuntimeError> {
+        if self.enums.contains_key(&def.name) {
+            return Err(RuntimeError::EnumRedeclaration(def.name));
+        }
+
+        self.enums.insert(def.name.clone(), Rc::new(def));
+        Ok(())
+    }
+
     pub fn declare_var(
         &mut self,
         name: &str,
@@ -81,6 +216,34 @@ impl Environment {
         Ok(())
     }
 
+    /// Declare a variable along with its type, the values assigned to it or
+    /// to its elements are then checked against that type.
+    pub fn declare_typed_var(
+        &mut self,
+        name: &str,
+        value: RuntimeVal,
+        typing: &Type,
+        is_const: bool,
+    ) -> Result<(), RuntimeError> {
+        self.declare_var(name, value, is_const)?;
+        self.types.insert(name.to_string(), typing.clone());
+
+        Ok(())
+    }
+
+    /// Type the variable was declared with, `Any` for variables declared
+    /// without one like function parameters.
+    pub fn get_var_type(&self, name: &str) -> Type {
+        if self.variables.contains_key(name) {
+            return self.types.get(name).cloned().unwrap_or(Type::Any);
+        }
+
+        match &self.parent {
+            Some(parent) => parent.borrow().get_var_type(name),
+            None => Type::Any,
+        }
+    }
+
     pub fn assign_var(&mut self, name: &str, value: RuntimeVal) -> Result<(), RuntimeError> {
         let old_var = self.variables.remove(name);
         match old_var {
@@ -101,5 +264,35 @@ impl Environment {
             .expect("Failed to initialize builtins");
         self.declare_var("false", RuntimeVal::Bool(false), true)
             .expect("Failed to initialize builtins");
+
+        // Value of the errors raised by the runtime and caught by scripts
+        self.structs
+            .insert(ERROR_STRUCT.to_string(), Rc::new(StructDef::error()));
+
+        self.declare_enum(EnumDef {
+            name: "Option".to_string(),
+            variants: vec![
+                ("Some".to_string(), vec![Type::Any]),
+                ("None".to_string(), vec![]),
+            ],
+        })
+        .expect("Failed to initialize builtins");
+        self.declare_enum(EnumDef {
+            name: "Result".to_string(),
+            variants: vec![
+                ("Ok".to_string(), vec![Type::Any]),
+                ("Err".to_string(), vec![Type::Any]),
+            ],
+        })
+        .expect("Failed to initialize builtins");
+
+        builtins::register(self);
+    }
+}
+
+// Environments are compared by identity, two scopes are never equal by content
+impl PartialEq for Environment {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
     }
 }
@@ -1,23 +1,78 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Instant,
+};
+
+use regex::Regex;
 
 use crate::{
-    ast::{atom::Atom, expr::Expr, program::Program, stmt::Stmt},
+    ast::{atom::Atom, expr::Expr, pattern::Pattern, program::Program, stmt::Stmt, types::Type},
     lexer::TokenType,
+    stack::ensure_sufficient_stack,
 };
 
 use super::{
+    enums::EnumDef,
     environment::{Env, Environment},
+    limits::ExecutionLimits,
+    map::{Map, MapKey},
+    operators::{self, NumericPolicy},
+    patterns::PatternCache,
+    structs::StructDef,
     values::{RuntimeError, RuntimeVal},
 };
 
-pub struct Interpreter {}
+pub struct Interpreter {
+    limits: ExecutionLimits,
+    numeric_policy: NumericPolicy,
+    // Number of user function frames currently being evaluated
+    call_depth: Cell<usize>,
+    // Number of `try` statements enclosing the code of the current frame
+    try_depth: Cell<usize>,
+    // Resources used by the current `evaluate_program` call
+    steps: Cell<u64>,
+    string_bytes: Cell<usize>,
+    deadline: Cell<Option<Instant>>,
+    // Kept across `evaluate_program` calls, like the global environment
+    patterns: RefCell<PatternCache>,
+}
 
 impl Interpreter {
+    pub fn new() -> Self {
+        Interpreter {
+            limits: ExecutionLimits::default(),
+            numeric_policy: NumericPolicy::default(),
+            call_depth: Cell::new(0),
+            try_depth: Cell::new(0),
+            steps: Cell::new(0),
+            string_bytes: Cell::new(0),
+            deadline: Cell::new(None),
+            patterns: RefCell::new(PatternCache::default()),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_numeric_policy(mut self, numeric_policy: NumericPolicy) -> Self {
+        self.numeric_policy = numeric_policy;
+        self
+    }
+
+    pub fn numeric_policy(&self) -> NumericPolicy {
+        self.numeric_policy
+    }
+
     pub fn evaluate_program(
         &self,
         program: &Program,
         env: Env,
     ) -> Result<RuntimeVal, RuntimeError> {
+        self.reset_usage();
+
         let mut result = RuntimeVal::Undefined;
 
         for stmt in &program.statements {
@@ -27,18 +82,151 @@ impl Interpreter {
         Ok(result)
     }
 
+    /// Call a script or native function from the host, e.g. one found with
+    /// `Environment::lookup_func`. Resources are counted the same way as for
+    /// `evaluate_program`.
+    pub fn call_function(
+        &self,
+        name: &str,
+        func: RuntimeVal,
+        args: Vec<RuntimeVal>,
+        scope: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        self.reset_usage();
+        self.enter_call(name, func, args, scope)
+    }
+
+    fn reset_usage(&self) {
+        self.steps.set(0);
+        self.string_bytes.set(0);
+        self.deadline.set(
+            self.limits
+                .max_duration
+                .map(|max_duration| Instant::now() + max_duration),
+        );
+    }
+
+    /// Count one evaluated node against the step and time limits.
+    fn step(&self) -> Result<(), RuntimeError> {
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+
+        if let Some(max_steps) = self.limits.max_steps {
+            if steps > max_steps {
+                return Err(RuntimeError::StepLimitExceeded(max_steps));
+            }
+        }
+
+        if let (Some(deadline), Some(max_duration)) =
+            (self.deadline.get(), self.limits.max_duration)
+        {
+            if Instant::now() >= deadline {
+                return Err(RuntimeError::TimeLimitExceeded(max_duration));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check a string of `bytes` bytes fits in the string limit before it is
+    /// allocated, it is only counted once created by `track_string`.
+    pub(crate) fn check_string_budget(&self, bytes: usize) -> Result<(), RuntimeError> {
+        if let Some(max_string_bytes) = self.limits.max_string_bytes {
+            if self.string_bytes.get().saturating_add(bytes) > max_string_bytes {
+                return Err(RuntimeError::StringLimitExceeded(max_string_bytes));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiled regex for a pattern, cached for the following calls.
+    pub(crate) fn regex(&self, pattern: &str) -> Result<Regex, RuntimeError> {
+        self.patterns.borrow_mut().get(pattern)
+    }
+
+    /// Count a newly created value against the string limit.
+    fn track_string(&self, val: RuntimeVal) -> Result<RuntimeVal, RuntimeError> {
+        if let RuntimeVal::String(string) = &val {
+            self.track_string_bytes(string.len())?;
+        }
+
+        Ok(val)
+    }
+
+    /// Count newly created strings that aren't the returned value, like the
+    /// elements of a list built by a builtin, against the string limit.
+    pub(crate) fn track_string_bytes(&self, bytes: usize) -> Result<(), RuntimeError> {
+        let string_bytes = self.string_bytes.get().saturating_add(bytes);
+        self.string_bytes.set(string_bytes);
+
+        if let Some(max_string_bytes) = self.limits.max_string_bytes {
+            if string_bytes > max_string_bytes {
+                return Err(RuntimeError::StringLimitExceeded(max_string_bytes));
+            }
+        }
+
+        Ok(())
+    }
+
     fn evaluate(&self, stmt: &Stmt, env: Env) -> Result<RuntimeVal, RuntimeError> {
+        self.step()?;
+
+        // Statements that can appear on the path of a recursive call are
+        // handled here, the others in a separate function so their locals
+        // don't take space in every frame
+        ensure_sufficient_stack(|| match stmt {
+            Stmt::Expression(expr) => self.evaluate_expr_stmt(expr, env),
+            Stmt::Block(stmts) => self.evaluate_block_stmt(stmts, env),
+            Stmt::Return(expr) => self.evaluate_return_stmt(expr, env),
+            Stmt::If(condition, then_branch, else_branch) => {
+                self.evaluate_if_stmt(condition, then_branch, else_branch.as_deref(), env)
+            }
+            stmt => self.evaluate_other_stmt(stmt, env),
+        })
+    }
+
+    fn evaluate_other_stmt(&self, stmt: &Stmt, env: Env) -> Result<RuntimeVal, RuntimeError> {
         match stmt {
-            Stmt::Expression(expr) => self.evaluate_expr(expr, env),
             Stmt::VarDeclaration(name, typing, is_const, expr) => {
                 self.evaluate_var_declaration_stmt(name, typing, is_const, expr, env)
             }
             Stmt::FuncDeclaration(name, parameters, body) => {
                 self.evaluate_func_declaration_stmt(name, parameters, body, env)
             }
-            Stmt::Block(stmts) => self.evaluate_block_stmt(stmts, env),
+            Stmt::StructDeclaration(name, fields) => {
+                self.evaluate_struct_declaration_stmt(name, fields, env)
+            }
+            Stmt::EnumDeclaration(name, variants) => {
+                self.evaluate_enum_declaration_stmt(name, variants, env)
+            }
+            Stmt::ImplDeclaration(type_name, methods) => {
+                self.evaluate_impl_declaration_stmt(type_name, methods, env)
+            }
             Stmt::Print(expr) => self.evaluate_print_expr(expr, env),
-            Stmt::Return(expr) => self.evaluate_return_stmt(expr, env),
+            Stmt::Throw(expr) => Err(RuntimeError::Thrown(self.evaluate_expr(expr, env)?)),
+            Stmt::Try(body, catch, finally) => {
+                self.evaluate_try_stmt(body, catch.as_ref(), finally.as_deref(), env)
+            }
+            Stmt::Expression(_) | Stmt::Block(_) | Stmt::Return(_) | Stmt::If(..) => {
+                self.evaluate(stmt, env)
+            }
+        }
+    }
+
+    /// Evaluate an expression whose value is discarded, which is the only place
+    /// a call to a function without `return` is allowed.
+    fn evaluate_expr_stmt(&self, expr: &Expr, env: Env) -> Result<RuntimeVal, RuntimeError> {
+        match expr {
+            Expr::CallExpr(name, params) => {
+                self.step()?;
+                self.evaluate_func_call_expr(name, params, env)
+            }
+            Expr::MethodCall(target, method, params) => {
+                self.step()?;
+                self.evaluate_method_call_expr(target, method, params, env)
+            }
+            _ => self.evaluate_expr(expr, env),
         }
     }
 
@@ -53,25 +241,189 @@ impl Interpreter {
     fn evaluate_func_declaration_stmt(
         &self,
         name: &str,
-        parameters: &Vec<String>,
+        parameters: &[String],
         body: &Stmt,
         env: Env,
     ) -> Result<RuntimeVal, RuntimeError> {
+        let func = Self::make_func(name, parameters, body);
+        env.borrow_mut().declare_func(name, func.clone())?;
+        Ok(func)
+    }
+
+    fn make_func(name: &str, parameters: &[String], body: &Stmt) -> RuntimeVal {
         match body {
-            Stmt::Block(value) => {
-                let func = RuntimeVal::Func(
-                    name.to_string(),
-                    parameters.clone(),
-                    Stmt::Block(value.clone()),
-                );
-                env.borrow_mut().declare_func(name, func.clone())?;
-                return Ok(func);
-            }
+            Stmt::Block(value) => RuntimeVal::Func(
+                name.to_string(),
+                parameters.to_vec(),
+                Rc::new(Stmt::Block(value.clone())),
+            ),
             _ => panic!("Invalid function body"),
         }
     }
 
+    fn evaluate_impl_declaration_stmt(
+        &self,
+        type_name: &str,
+        methods: &[Stmt],
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        // Methods can be implemented on both structs and enums
+        if env.borrow().get_enum(type_name).is_err() {
+            env.borrow().get_struct(type_name)?;
+        }
+
+        for method in methods {
+            match method {
+                Stmt::FuncDeclaration(name, parameters, body) => {
+                    let func = Self::make_func(&format!("{type_name}.{name}"), parameters, body);
+                    env.borrow_mut().declare_method(type_name, name, func)?;
+                }
+                _ => panic!("Invalid impl item"),
+            }
+        }
+
+        Ok(RuntimeVal::Undefined)
+    }
+
+    fn evaluate_struct_declaration_stmt(
+        &self,
+        name: &str,
+        fields: &[(String, Type)],
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        env.borrow_mut().declare_struct(StructDef {
+            name: name.to_string(),
+            fields: fields.to_vec(),
+        })?;
+
+        Ok(RuntimeVal::Undefined)
+    }
+
+    fn evaluate_enum_declaration_stmt(
+        &self,
+        name: &str,
+        variants: &[(String, Vec<Type>)],
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        env.borrow_mut().declare_enum(EnumDef {
+            name: name.to_string(),
+            variants: variants.to_vec(),
+        })?;
+
+        Ok(RuntimeVal::Undefined)
+    }
+
+    fn evaluate_if_stmt(
+        &self,
+        condition: &Expr,
+        then_branch: &Stmt,
+        else_branch: Option<&Stmt>,
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let branch = match self.evaluate_expr(condition, Rc::clone(&env))? {
+            RuntimeVal::Bool(true) => then_branch,
+            RuntimeVal::Bool(false) => match else_branch {
+                Some(branch) => branch,
+                None => return Ok(RuntimeVal::Undefined),
+            },
+            _ => return Err(RuntimeError::InvalidType),
+        };
+
+        self.evaluate_branch(branch, env)
+    }
+
+    /// Unwrap the branch block so a `return` inside it reaches the enclosing block.
+    fn evaluate_branch(&self, branch: &Stmt, env: Env) -> Result<RuntimeVal, RuntimeError> {
+        match self.evaluate(branch, env)? {
+            RuntimeVal::Block(val) => Ok(*val),
+            val => Ok(val),
+        }
+    }
+
+    fn evaluate_try_stmt(
+        &self,
+        body: &Stmt,
+        catch: Option<&(String, Box<Stmt>)>,
+        finally: Option<&Stmt>,
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        // Calls can't be deferred as tail calls out of the `try`, their errors
+        // must be raised while it is still able to catch them
+        self.try_depth.set(self.try_depth.get() + 1);
+        let result = self.evaluate_try_blocks(body, catch, finally, env);
+        self.try_depth.set(self.try_depth.get() - 1);
+
+        result
+    }
+
+    fn evaluate_try_blocks(
+        &self,
+        body: &Stmt,
+        catch: Option<&(String, Box<Stmt>)>,
+        finally: Option<&Stmt>,
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let result = match (self.evaluate_branch(body, Rc::clone(&env)), catch) {
+            (Err(err), Some((name, block))) if err.is_catchable() => {
+                let catch_env = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(&env)))));
+                let val = self.error_value(err)?;
+                catch_env.borrow_mut().declare_var(name, val, false)?;
+                self.evaluate_branch(block, catch_env)
+            }
+            (result, _) => result,
+        };
+
+        // A `return` or an error in the `finally` block replaces the result
+        match finally {
+            Some(block) => match self.evaluate_branch(block, env)? {
+                val @ RuntimeVal::Return(_) => Ok(val),
+                _ => result,
+            },
+            None => result,
+        }
+    }
+
+    /// Value bound by `catch`: the thrown value itself, or an `Error` struct
+    /// with the kind and message of a runtime error.
+    fn error_value(&self, err: RuntimeError) -> Result<RuntimeVal, RuntimeError> {
+        if let RuntimeError::Thrown(val) = err {
+            return Ok(val);
+        }
+
+        let instance = StructDef::error().instantiate(vec![
+            (
+                "kind".to_string(),
+                RuntimeVal::String(err.kind().to_string()),
+            ),
+            ("message".to_string(), RuntimeVal::String(err.to_string())),
+        ])?;
+
+        Ok(RuntimeVal::Struct(Rc::new(RefCell::new(instance))))
+    }
+
     fn evaluate_return_stmt(&self, expr: &Expr, env: Env) -> Result<RuntimeVal, RuntimeError> {
+        // A call in tail position is handed back to the calling frame instead of
+        // being evaluated here, so tail recursion does not grow the Rust stack
+        if self.call_depth.get() > 0 && self.try_depth.get() == 0 {
+            let call = match expr {
+                Expr::CallExpr(name, params) => {
+                    Some(self.resolve_func_call(name, params, env.clone())?)
+                }
+                Expr::MethodCall(target, method, params) => {
+                    Some(self.resolve_method_call(target, method, params, env.clone())?)
+                }
+                _ => None,
+            };
+
+            if let Some((func, args, scope)) = call {
+                return Ok(RuntimeVal::Return(Box::new(RuntimeVal::TailCall(
+                    Box::new(func),
+                    args,
+                    scope,
+                ))));
+            }
+        }
+
         let val = self.evaluate_expr(expr, env)?;
         Ok(RuntimeVal::Return(Box::new(val)))
     }
@@ -98,205 +450,609 @@ impl Interpreter {
     fn evaluate_var_declaration_stmt(
         &self,
         name: &str,
-        typing: &TokenType,
+        typing: &Type,
         is_const: &bool,
         expr: &Expr,
         env: Env,
     ) -> Result<RuntimeVal, RuntimeError> {
         let value = self.evaluate_expr(expr, Rc::clone(&env))?;
 
-        match value {
-            RuntimeVal::Int(_) => {
-                if *typing != TokenType::Integer {
-                    return Err(RuntimeError::InvalidType);
-                }
-            }
-            RuntimeVal::Float(_) => {
-                if *typing != TokenType::Float {
-                    return Err(RuntimeError::InvalidType);
-                }
-            }
-            RuntimeVal::String(_) => {
-                if *typing != TokenType::String {
-                    return Err(RuntimeError::InvalidType);
-                }
-            }
-            RuntimeVal::Bool(_) => {
-                if *typing != TokenType::Bool {
-                    return Err(RuntimeError::InvalidType);
-                }
-            }
-            _ => {}
+        if !value.matches_type(typing) {
+            return Err(RuntimeError::InvalidType);
         }
 
-        env.borrow_mut().declare_var(name, value, *is_const)?;
+        env.borrow_mut()
+            .declare_typed_var(name, value, typing, *is_const)?;
         Ok(RuntimeVal::Undefined)
     }
 
     fn evaluate_expr(&self, expr: &Expr, env: Env) -> Result<RuntimeVal, RuntimeError> {
-        match expr {
+        self.step()?;
+
+        // Same split as `evaluate`, calls and the expressions around them are
+        // handled here
+        ensure_sufficient_stack(|| match expr {
             Expr::Binary(lhs, op, rhs) => self.evaluate_binary_expr(lhs, op, rhs, env),
-            Expr::CallExpr(name, params) => self.evaluate_func_call_expr(name, params, env),
-            Expr::Assignment(lhs, _, rhs) => self.evaluate_assignment_expr(lhs, rhs, env),
+            Expr::CallExpr(name, params) => {
+                Self::returned_value(name, self.evaluate_func_call_expr(name, params, env)?)
+            }
+            Expr::MethodCall(target, method, params) => Self::returned_value(
+                method,
+                self.evaluate_method_call_expr(target, method, params, env)?,
+            ),
             Expr::Identifier(name) => self.evaluate_identifier(name, env),
             Expr::Literal(val) => self.evaluate_literal(val),
+            expr => self.evaluate_other_expr(expr, env),
+        })
+    }
+
+    fn evaluate_other_expr(&self, expr: &Expr, env: Env) -> Result<RuntimeVal, RuntimeError> {
+        match expr {
+            Expr::Assignment(lhs, op, rhs) => self.evaluate_assignment_expr(lhs, op, rhs, env),
+            Expr::List(elements) => self.evaluate_list_expr(elements, env),
+            Expr::Map(entries) => self.evaluate_map_expr(entries, env),
+            Expr::Index(target, index) => self.evaluate_index_expr(target, index, env),
+            Expr::StructLiteral(name, fields) => self.evaluate_struct_literal(name, fields, env),
+            Expr::Member(target, field) => self.evaluate_member_expr(target, field, env),
+            Expr::Try(target) => self.evaluate_try_expr(target, env),
+            Expr::Variant(name, variant, params) => {
+                self.evaluate_variant_expr(name, variant, params, env)
+            }
+            Expr::Match(subject, arms) => self.evaluate_match_expr(subject, arms, env),
+            Expr::Binary(..)
+            | Expr::CallExpr(..)
+            | Expr::MethodCall(..)
+            | Expr::Identifier(_)
+            | Expr::Literal(_) => self.evaluate_expr(expr, env),
         }
     }
 
-    fn evaluate_func_call_expr(
+    fn returned_value(name: &str, val: RuntimeVal) -> Result<RuntimeVal, RuntimeError> {
+        match val {
+            RuntimeVal::Undefined => Err(RuntimeError::MissingReturnValue(name.to_string())),
+            val => Ok(val),
+        }
+    }
+
+    /// Unwrap a `Some` or `Ok`, a `None` or `Err` is handed back to the
+    /// enclosing call through `RuntimeError::Propagated`.
+    fn evaluate_try_expr(&self, target: &Expr, env: Env) -> Result<RuntimeVal, RuntimeError> {
+        match self.evaluate_expr(target, env)? {
+            RuntimeVal::Enum(name, variant, mut fields) if name == "Option" || name == "Result" => {
+                match variant.as_str() {
+                    "Some" | "Ok" => Ok(fields.remove(0)),
+                    _ => Err(RuntimeError::Propagated(RuntimeVal::Enum(
+                        name, variant, fields,
+                    ))),
+                }
+            }
+            _ => Err(RuntimeError::InvalidOperandType),
+        }
+    }
+
+    fn evaluate_variant_expr(
         &self,
         name: &str,
-        params: &Vec<Expr>,
+        variant: &str,
+        params: &[Expr],
         env: Env,
     ) -> Result<RuntimeVal, RuntimeError> {
-        let func = env.borrow().get_func(name)?;
-        match func {
-            RuntimeVal::Func(_, func_params, body) => {
-                if func_params.len() != params.len() {
-                    return Err(RuntimeError::InvalidFuncCallParametersCount(
-                        name.to_string(),
-                    ));
-                }
+        let def = env.borrow().get_enum(name);
+        let def = match def {
+            Ok(def) => def,
+            Err(err) => return self.evaluate_module_member(name, variant, params, env, err),
+        };
+        let values = self.evaluate_args(params, env)?;
 
-                let block_env = Rc::new(RefCell::new(Environment::new(None)));
-                for (i, param_name) in func_params.iter().enumerate() {
-                    let param_value = self.evaluate_expr(&params[i], Rc::clone(&block_env))?;
-                    block_env
-                        .borrow_mut()
-                        .declare_var(param_name, param_value, false)?;
-                }
+        def.instantiate(variant, values)
+    }
 
-                let result = self.evaluate(&body, block_env)?;
-                match result {
-                    RuntimeVal::Block(val) => match *val {
-                        RuntimeVal::Return(val) => Ok(*val),
-                        _ => Ok(RuntimeVal::Undefined),
-                    },
-                    _ => panic!("Expected block"),
-                }
-            }
-            _ => panic!("Expected a function"),
+    /// `module::member` when no enum is named `module`: a call of a native
+    /// function or a constant registered under the qualified name, e.g.
+    /// `math::sqrt(2.0)` or `math::PI`. `err` is raised if there is neither.
+    fn evaluate_module_member(
+        &self,
+        module: &str,
+        member: &str,
+        params: &[Expr],
+        env: Env,
+        err: RuntimeError,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let name = format!("{module}::{member}");
+        if let Ok((func, scope)) = Environment::lookup_func(&env, &name) {
+            let args = self.evaluate_args(params, env)?;
+            return Self::returned_value(&name, self.enter_call(&name, func, args, scope)?);
+        }
+
+        match env.borrow().get_var(&name) {
+            Ok(val) if params.is_empty() => Ok(val),
+            _ => Err(err),
         }
     }
 
-    fn evaluate_assignment_expr(
+    /// Evaluate the first arm whose pattern matches the subject, bindings of
+    /// the pattern are only visible in the arm's expression.
+    fn evaluate_match_expr(
         &self,
-        lhs: &Expr,
-        rhs: &Expr,
+        subject: &Expr,
+        arms: &[(Pattern, Expr)],
         env: Env,
     ) -> Result<RuntimeVal, RuntimeError> {
-        match lhs {
-            Expr::Identifier(name) => {
-                let val = self.evaluate_expr(rhs, Rc::clone(&env))?;
-                env.borrow_mut().assign_var(name, val)?;
-                Ok(RuntimeVal::Undefined)
+        let val = self.evaluate_expr(subject, Rc::clone(&env))?;
+
+        for (pattern, expr) in arms {
+            let arm_env = Rc::new(RefCell::new(Environment::new(Some(Rc::clone(&env)))));
+            if self.match_pattern(pattern, &val, &arm_env)? {
+                return self.evaluate_expr(expr, arm_env);
             }
-            _ => Err(RuntimeError::InvalidOperandType),
         }
-    }
 
-    fn evaluate_identifier(&self, name: &str, env: Env) -> Result<RuntimeVal, RuntimeError> {
-        env.borrow().get_var(name)
+        Err(RuntimeError::NoMatch(val.to_string()))
     }
 
-    fn evaluate_binary_expr(
+    fn match_pattern(
         &self,
-        lhs: &Expr,
-        op: &TokenType,
-        rhs: &Expr,
-        env: Env,
-    ) -> Result<RuntimeVal, RuntimeError> {
-        let left = self.evaluate_expr(lhs, Rc::clone(&env))?;
-        let right = self.evaluate_expr(rhs, Rc::clone(&env))?;
+        pattern: &Pattern,
+        val: &RuntimeVal,
+        env: &Env,
+    ) -> Result<bool, RuntimeError> {
+        match pattern {
+            Pattern::Wildcard => Ok(true),
+            Pattern::Binding(name) => {
+                env.borrow_mut().declare_var(name, val.clone(), false)?;
+                Ok(true)
+            }
+            // Values of another type simply don't match the literal
+            Pattern::Literal(atom) => Ok(matches!(
+                operators::binary_op(
+                    RuntimeVal::from(atom),
+                    &TokenType::EqualEqual,
+                    val.clone(),
+                    self.numeric_policy
+                ),
+                Ok(RuntimeVal::Bool(true))
+            )),
+            Pattern::Variant(name, variant, patterns) => {
+                let def = env.borrow().get_enum(name)?;
+                let full_name = format!("{name}::{variant}");
+                let (_, types) = def
+                    .variants
+                    .iter()
+                    .find(|(declared, _)| declared == variant)
+                    .ok_or_else(|| RuntimeError::UndefinedVariant(full_name.clone()))?;
+                if types.len() != patterns.len() {
+                    return Err(RuntimeError::InvalidFuncCallParametersCount(full_name));
+                }
 
-        match left {
-            RuntimeVal::Int(left) => match right {
-                RuntimeVal::Int(right) => match op {
-                    TokenType::Plus => Ok(RuntimeVal::Int(left + right)),
-                    TokenType::Minus => Ok(RuntimeVal::Int(left - right)),
-                    TokenType::Star => Ok(RuntimeVal::Int(left * right)),
-                    TokenType::Slash => {
-                        if right == 0 {
-                            return Err(RuntimeError::DivisionByZero);
-                        }
-                        Ok(RuntimeVal::Int(left / right))
-                    }
-                    _ => Err(RuntimeError::InvalidOperandType),
-                },
-                RuntimeVal::Float(right) => match op {
-                    TokenType::Plus => Ok(RuntimeVal::Float(left as f64 + right)),
-                    TokenType::Minus => Ok(RuntimeVal::Float(left as f64 - right)),
-                    TokenType::Star => Ok(RuntimeVal::Float(left as f64 * right)),
-                    TokenType::Slash => {
-                        if right == 0.0 {
-                            return Err(RuntimeError::DivisionByZero);
+                match val {
+                    RuntimeVal::Enum(enum_name, enum_variant, fields)
+                        if enum_name == name && enum_variant == variant =>
+                    {
+                        for (pattern, field) in patterns.iter().zip(fields) {
+                            if !self.match_pattern(pattern, field, env)? {
+                                return Ok(false);
+                            }
                         }
-                        Ok(RuntimeVal::Float(left as f64 / right))
+                        Ok(true)
                     }
-                    _ => Err(RuntimeError::InvalidOperandType),
-                },
-                _ => Err(RuntimeError::InvalidOperandType),
-            },
-            RuntimeVal::Float(left) => match right {
-                RuntimeVal::Int(right) => match op {
-                    TokenType::Plus => Ok(RuntimeVal::Float(left + (right as f64))),
-                    TokenType::Minus => Ok(RuntimeVal::Float(left - (right as f64))),
-                    TokenType::Star => Ok(RuntimeVal::Float(left * (right as f64))),
-                    TokenType::Slash => Ok(RuntimeVal::Float(left / (right as f64))),
-                    _ => Err(RuntimeError::InvalidOperandType),
-                },
-                RuntimeVal::Float(right) => match op {
-                    TokenType::Plus => Ok(RuntimeVal::Float(left + right)),
-                    TokenType::Minus => Ok(RuntimeVal::Float(left - right)),
-                    TokenType::Star => Ok(RuntimeVal::Float(left * right)),
-                    TokenType::Slash => Ok(RuntimeVal::Float(left / right)),
-                    _ => Err(RuntimeError::InvalidOperandType),
-                },
-                _ => Err(RuntimeError::InvalidOperandType),
-            },
-            RuntimeVal::String(left) => match right {
-                RuntimeVal::String(right) => match op {
-                    TokenType::Plus => Ok(RuntimeVal::String(left + &right)),
-                    _ => Err(RuntimeError::InvalidOperandType),
-                },
-                _ => Err(RuntimeError::InvalidOperandType),
-            },
-            _ => unimplemented!("Binary expression not implemented for {:?}", left),
+                    _ => Ok(false),
+                }
+            }
         }
     }
 
-    fn evaluate_literal(&self, val: &Atom) -> Result<RuntimeVal, RuntimeError> {
-        match val {
-            Atom::Integer(num) => Ok(RuntimeVal::Int(*num)),
-            Atom::Float(num) => Ok(RuntimeVal::Float(*num)),
-            Atom::String(string) => Ok(RuntimeVal::String(string.clone())),
-            Atom::Bool(val) => Ok(RuntimeVal::Bool(*val)),
-        }
-    }
-}
+    fn evaluate_struct_literal(
+        &self,
+        name: &str,
+        fields: &[(String, Expr)],
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let def = env.borrow().get_struct(name)?;
+        let values = fields
+            .iter()
+            .map(|(field, expr)| Ok((field.clone(), self.evaluate_expr(expr, Rc::clone(&env))?)))
+            .collect::<Result<Vec<_>, RuntimeError>>()?;
 
-#[cfg(test)]
-mod test {
-    use std::{cell::RefCell, rc::Rc};
+        Ok(RuntimeVal::Struct(Rc::new(RefCell::new(
+            def.instantiate(values)?,
+        ))))
+    }
 
-    use crate::{parser::Parser, runtime::environment::Environment};
+    fn evaluate_member_expr(
+        &self,
+        target: &Expr,
+        field: &str,
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        match self.evaluate_expr(target, env)? {
+            RuntimeVal::Struct(instance) => instance.borrow().get(field),
+            _ => Err(RuntimeError::InvalidOperandType),
+        }
+    }
 
-    use super::*;
+    fn evaluate_list_expr(&self, elements: &[Expr], env: Env) -> Result<RuntimeVal, RuntimeError> {
+        let elements = elements
+            .iter()
+            .map(|element| self.evaluate_expr(element, Rc::clone(&env)))
+            .collect::<Result<Vec<_>, _>>()?;
 
-    fn evaluate(source: &str) -> Result<RuntimeVal, RuntimeError> {
-        let mut parser = Parser::new(source);
-        let program = parser.parse();
+        Ok(RuntimeVal::new_list(elements))
+    }
 
-        let env = Rc::new(RefCell::new(Environment::new(None)));
-        let runtime = Interpreter {};
+    fn evaluate_map_expr(
+        &self,
+        entries: &[(Expr, Expr)],
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let mut map = Map::default();
+        for (key, value) in entries {
+            let key = MapKey::try_from(self.evaluate_expr(key, Rc::clone(&env))?)?;
+            let value = self.evaluate_expr(value, Rc::clone(&env))?;
+            map.insert(key, value);
+        }
 
-        runtime.evaluate_program(&program, env)
+        Ok(RuntimeVal::new_map(map))
     }
 
-    #[test]
-    fn additive_expr() {
-        let result = evaluate("1 + 1").expect("Failed to evaluate");
-        assert_eq!(result, RuntimeVal::Int(2));
+    fn evaluate_index_expr(
+        &self,
+        target: &Expr,
+        index: &Expr,
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let target = self.evaluate_expr(target, Rc::clone(&env))?;
+        let index = self.evaluate_expr(index, env)?;
+
+        Self::index_value(&target, &index)
+    }
+
+    fn index_value(target: &RuntimeVal, index: &RuntimeVal) -> Result<RuntimeVal, RuntimeError> {
+        match (target, index) {
+            (RuntimeVal::List(elements), RuntimeVal::Int(index)) => {
+                let elements = elements.borrow();
+                let position = Self::list_position(*index, elements.len())?;
+                Ok(elements[position].clone())
+            }
+            (RuntimeVal::Map(map), key) => {
+                let key = MapKey::try_from(key.clone())?;
+                match map.borrow().get(&key) {
+                    Some(val) => Ok(val.clone()),
+                    None => Err(RuntimeError::UndefinedKey(
+                        RuntimeVal::from(&key).to_string(),
+                    )),
+                }
+            }
+            _ => Err(RuntimeError::InvalidOperandType),
+        }
+    }
+
+    /// Bounds check a script index against a list length.
+    fn list_position(index: i64, len: usize) -> Result<usize, RuntimeError> {
+        match usize::try_from(index) {
+            Ok(position) if position < len => Ok(position),
+            _ => Err(RuntimeError::IndexOutOfBounds { index, len }),
+        }
+    }
+
+    fn evaluate_func_call_expr(
+        &self,
+        name: &str,
+        params: &[Expr],
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let (func, args, scope) = self.resolve_func_call(name, params, env)?;
+        self.enter_call(name, func, args, scope)
+    }
+
+    fn evaluate_method_call_expr(
+        &self,
+        target: &Expr,
+        method: &str,
+        params: &[Expr],
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let (func, args, scope) = self.resolve_method_call(target, method, params, env)?;
+        let name = match &func {
+            RuntimeVal::Func(name, ..) => name.clone(),
+            _ => method.to_string(),
+        };
+
+        self.enter_call(&name, func, args, scope)
+    }
+
+    /// Find the called function and evaluate its arguments in the caller scope.
+    fn resolve_func_call(
+        &self,
+        name: &str,
+        params: &[Expr],
+        env: Env,
+    ) -> Result<(RuntimeVal, Vec<RuntimeVal>, Env), RuntimeError> {
+        let (func, scope) = Environment::lookup_func(&env, name)?;
+        let args = self.evaluate_args(params, env)?;
+
+        Ok((func, args, scope))
+    }
+
+    /// Find the method of the receiver's type, the receiver is passed as the
+    /// first argument and bound to the method's `self` parameter.
+    fn resolve_method_call(
+        &self,
+        target: &Expr,
+        method: &str,
+        params: &[Expr],
+        env: Env,
+    ) -> Result<(RuntimeVal, Vec<RuntimeVal>, Env), RuntimeError> {
+        let receiver = self.evaluate_expr(target, Rc::clone(&env))?;
+        let type_name = match &receiver {
+            RuntimeVal::Struct(instance) => instance.borrow().def.name.clone(),
+            RuntimeVal::Enum(name, ..) => name.clone(),
+            val => val.type_name().to_string(),
+        };
+
+        let (func, scope) = Environment::lookup_method(&env, &type_name, method)?;
+        let mut args = vec![receiver];
+        args.extend(self.evaluate_args(params, env)?);
+
+        Ok((func, args, scope))
+    }
+
+    fn enter_call(
+        &self,
+        name: &str,
+        func: RuntimeVal,
+        args: Vec<RuntimeVal>,
+        scope: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let depth = self.call_depth.get();
+        if depth >= self.limits.max_call_depth {
+            return Err(RuntimeError::StackOverflow {
+                depth: depth + 1,
+                function: name.to_string(),
+            });
+        }
+        // The callee's own frame starts outside of any `try`
+        let try_depth = self.try_depth.replace(0);
+        self.call_depth.set(depth + 1);
+        let result = self.call_func(func, args, scope);
+        self.call_depth.set(depth);
+        self.try_depth.set(try_depth);
+
+        result
+    }
+
+    fn evaluate_args(&self, params: &[Expr], env: Env) -> Result<Vec<RuntimeVal>, RuntimeError> {
+        params
+            .iter()
+            .map(|param| self.evaluate_expr(param, Rc::clone(&env)))
+            .collect()
+    }
+
+    fn call_func(
+        &self,
+        mut func: RuntimeVal,
+        mut args: Vec<RuntimeVal>,
+        mut scope: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        // Tail calls replace the current frame and loop instead of recursing
+        loop {
+            let (name, func_params, body) = match func {
+                RuntimeVal::Func(name, func_params, body) => (name, func_params, body),
+                RuntimeVal::NativeFunc(native) => {
+                    return self.track_string(native.call(self, &args)?)
+                }
+                _ => panic!("Expected a function"),
+            };
+
+            if func_params.len() != args.len() {
+                return Err(RuntimeError::InvalidFuncCallParametersCount(name));
+            }
+
+            let block_env = Rc::new(RefCell::new(Environment::new(Some(scope))));
+            for (param_name, param_value) in func_params.iter().zip(args) {
+                block_env
+                    .borrow_mut()
+                    .declare_var(param_name, param_value, false)?;
+            }
+
+            let result = match self.evaluate(&body, block_env) {
+                Err(RuntimeError::Propagated(val)) => return Ok(val),
+                result => result?,
+            };
+            let val = match result {
+                RuntimeVal::Block(val) => match *val {
+                    RuntimeVal::Return(val) => *val,
+                    _ => return Ok(RuntimeVal::Undefined),
+                },
+                _ => panic!("Expected block"),
+            };
+
+            match val {
+                RuntimeVal::TailCall(next_func, next_args, next_scope) => {
+                    func = *next_func;
+                    args = next_args;
+                    scope = next_scope;
+                }
+                _ => return Ok(val),
+            }
+        }
+    }
+
+    /// Sub-expressions of the target are evaluated once, before the value,
+    /// including for compound assignments which read the target first.
+    fn evaluate_assignment_expr(
+        &self,
+        lhs: &Expr,
+        op: &TokenType,
+        rhs: &Expr,
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        match lhs {
+            Expr::Identifier(name) => {
+                let val = self.assigned_value(op, || env.borrow().get_var(name), rhs, &env)?;
+                if !val.matches_type(&Self::declared_type(lhs, &env)) {
+                    return Err(RuntimeError::InvalidType);
+                }
+
+                env.borrow_mut().assign_var(name, val)?;
+                Ok(RuntimeVal::Undefined)
+            }
+            Expr::Index(target_expr, index) => {
+                let typing = Self::declared_type(target_expr, &env);
+                let target = self.evaluate_expr(target_expr, Rc::clone(&env))?;
+                let index = self.evaluate_expr(index, Rc::clone(&env))?;
+                let val =
+                    self.assigned_value(op, || Self::index_value(&target, &index), rhs, &env)?;
+                if val.refers_to(&target) {
+                    return Err(RuntimeError::CyclicValue(target.type_name().to_string()));
+                }
+
+                match (target, index) {
+                    (RuntimeVal::List(elements), RuntimeVal::Int(index)) => {
+                        let mut elements = elements.borrow_mut();
+                        let position = Self::list_position(index, elements.len())?;
+                        if let Type::List(element_type) = &typing {
+                            if !val.matches_type(element_type) {
+                                return Err(RuntimeError::InvalidType);
+                            }
+                        }
+
+                        elements[position] = val;
+                        Ok(RuntimeVal::Undefined)
+                    }
+                    (RuntimeVal::Map(map), key) => {
+                        let key = MapKey::try_from(key)?;
+                        if let Type::Map(key_type, value_type) = &typing {
+                            if !RuntimeVal::from(&key).matches_type(key_type)
+                                || !val.matches_type(value_type)
+                            {
+                                return Err(RuntimeError::InvalidType);
+                            }
+                        }
+
+                        map.borrow_mut().insert(key, val);
+                        Ok(RuntimeVal::Undefined)
+                    }
+                    _ => Err(RuntimeError::InvalidOperandType),
+                }
+            }
+            Expr::Member(target, field) => {
+                let target = self.evaluate_expr(target, Rc::clone(&env))?;
+                let current = || match &target {
+                    RuntimeVal::Struct(instance) => instance.borrow().get(field),
+                    _ => Err(RuntimeError::InvalidOperandType),
+                };
+                let val = self.assigned_value(op, current, rhs, &env)?;
+                if val.refers_to(&target) {
+                    return Err(RuntimeError::CyclicValue(target.type_name().to_string()));
+                }
+
+                match target {
+                    RuntimeVal::Struct(instance) => {
+                        instance.borrow_mut().set(field, val)?;
+                        Ok(RuntimeVal::Undefined)
+                    }
+                    _ => Err(RuntimeError::InvalidOperandType),
+                }
+            }
+            _ => Err(RuntimeError::InvalidOperandType),
+        }
+    }
+
+    /// Type of an assignment target as declared, through the variable holding
+    /// it and the element types of the lists and maps it is nested in. `Any`
+    /// when it isn't known, like for a function parameter, struct fields
+    /// check their own type.
+    fn declared_type(target: &Expr, env: &Env) -> Type {
+        match target {
+            Expr::Identifier(name) => env.borrow().get_var_type(name),
+            Expr::Index(target, _) => match Self::declared_type(target, env) {
+                Type::List(element_type) => *element_type,
+                Type::Map(_, value_type) => *value_type,
+                _ => Type::Any,
+            },
+            _ => Type::Any,
+        }
+    }
+
+    /// Value stored by an assignment, a compound assignment reads the current
+    /// value of its target before evaluating the right-hand side.
+    fn assigned_value(
+        &self,
+        op: &TokenType,
+        current: impl FnOnce() -> Result<RuntimeVal, RuntimeError>,
+        rhs: &Expr,
+        env: &Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let op = match operators::compound_op(op) {
+            Some(op) => op,
+            None => return self.evaluate_expr(rhs, Rc::clone(env)),
+        };
+
+        let current = current()?;
+        let val = self.evaluate_expr(rhs, Rc::clone(env))?;
+        self.track_string(operators::binary_op(
+            current,
+            &op,
+            val,
+            self.numeric_policy,
+        )?)
+    }
+
+    fn evaluate_identifier(&self, name: &str, env: Env) -> Result<RuntimeVal, RuntimeError> {
+        env.borrow().get_var(name)
+    }
+
+    fn evaluate_binary_expr(
+        &self,
+        lhs: &Expr,
+        op: &TokenType,
+        rhs: &Expr,
+        env: Env,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let left = self.evaluate_expr(lhs, Rc::clone(&env))?;
+        let right = self.evaluate_expr(rhs, Rc::clone(&env))?;
+
+        self.track_string(operators::binary_op(left, op, right, self.numeric_policy)?)
+    }
+
+    fn evaluate_literal(&self, val: &Atom) -> Result<RuntimeVal, RuntimeError> {
+        self.track_string(RuntimeVal::from(val))
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use std::time::Duration;
+
+    use crate::{
+        parser::Parser,
+        runtime::{environment::Environment, limits::DEFAULT_MAX_CALL_DEPTH, native::NativeFunc},
+    };
+
+    use super::*;
+
+    fn evaluate(source: &str) -> Result<RuntimeVal, RuntimeError> {
+        let mut parser = Parser::new(source);
+        let program = parser.parse().unwrap();
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let runtime = Interpreter::new();
+
+        runtime.evaluate_program(&program, env)
+    }
+
+    #[test]
+    fn additive_expr() {
+        let result = evaluate("1 + 1").expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(2));
     }
 
     #[test]
@@ -337,6 +1093,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::err_expect)]
     fn var_declaration_invalid_type() {
         let result = evaluate("let t: int = 1 + 1.1;");
         assert!(result.is_err());
@@ -394,4 +1151,1621 @@ mod test {
         .expect("Failed to evaluate");
         assert_eq!(result, RuntimeVal::String("hello, world!".to_string()));
     }
+
+    #[test]
+    fn comparison_expr() {
+        let result = evaluate("1 + 1 == 2").expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Bool(true));
+
+        let result = evaluate("2.5 < 2").expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Bool(false));
+    }
+
+    #[test]
+    fn if_else() {
+        let result = evaluate(
+            "
+        func sign(n) {
+            if n < 0 {
+                return 'negative';
+            } else if n == 0 {
+                return 'zero';
+            } else {
+                return 'positive';
+            }
+        }
+
+        sign(0 - 5) + sign(0) + sign(5)
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result,
+            RuntimeVal::String("negativezeropositive".to_string())
+        );
+    }
+
+    #[test]
+    fn func_call_uses_caller_scope_for_arguments() {
+        let result = evaluate(
+            "
+        func double(n) {
+            return n * 2;
+        }
+
+        let x: int = 21;
+        double(x)
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(42));
+    }
+
+    #[test]
+    fn recursive_func_call() {
+        let result = evaluate(
+            "
+        func fact(n) {
+            if n <= 1 {
+                return 1;
+            }
+            return n * fact(n - 1);
+        }
+
+        fact(10)
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(3628800));
+    }
+
+    #[test]
+    fn tail_call_runs_in_constant_stack() {
+        let result = evaluate(
+            "
+        func countdown(n) {
+            if n == 0 {
+                return 'done';
+            }
+            return countdown(n - 1);
+        }
+
+        countdown(1000000)
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::String("done".to_string()));
+    }
+
+    #[test]
+    fn stack_overflow() {
+        let mut parser = Parser::new(
+            "
+        func forever(n) {
+            return 1 + forever(n + 1);
+        }
+
+        forever(0)
+        ",
+        );
+        let program = parser.parse().unwrap();
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let runtime = Interpreter::new().with_limits(ExecutionLimits {
+            max_call_depth: 50,
+            ..Default::default()
+        });
+
+        let result = runtime.evaluate_program(&program, env);
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::StackOverflow {
+                depth: 51,
+                function: "forever".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn stack_overflow_default_limit() {
+        let result = evaluate(
+            "
+        func forever(n) {
+            return 1 + forever(n + 1);
+        }
+
+        forever(0)
+        ",
+        );
+        assert!(matches!(
+            result,
+            Err(RuntimeError::StackOverflow { depth, .. }) if depth == DEFAULT_MAX_CALL_DEPTH + 1
+        ));
+
+        // Calls nested in branches and match arms use larger frames
+        let result = evaluate(
+            "
+        func forever(n) {
+            if n >= 0 {
+                return match Option::Some(n) {
+                    Option::Some(x) => forever(x + 1) + 1,
+                    Option::None => 0,
+                };
+            }
+            return 0;
+        }
+
+        forever(0)
+        ",
+        );
+        assert!(matches!(
+            result,
+            Err(RuntimeError::StackOverflow { depth, .. }) if depth == DEFAULT_MAX_CALL_DEPTH + 1
+        ));
+    }
+
+    #[test]
+    fn deep_nesting_on_small_stack() {
+        // Deeply nested expressions in a deep recursion, on the default stack
+        // size of a spawned thread
+        let source = format!(
+            "
+        func sum(n) {{
+            if n == 0 {{
+                return 0;
+            }}
+            return {}sum(n - 1){};
+        }}
+
+        sum({})
+        ",
+            "1 + (".repeat(100),
+            ")".repeat(100),
+            DEFAULT_MAX_CALL_DEPTH - 1
+        );
+
+        std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || {
+                let expected = 100 * (DEFAULT_MAX_CALL_DEPTH as i64 - 1);
+                assert_eq!(evaluate(&source), Ok(RuntimeVal::Int(expected)));
+            })
+            .expect("Failed to spawn thread")
+            .join()
+            .expect("Evaluation failed");
+    }
+
+    #[test]
+    fn tail_calls_do_not_count_toward_depth() {
+        let mut parser = Parser::new(
+            "
+        func countdown(n) {
+            if n == 0 {
+                return 'done';
+            }
+            return countdown(n - 1);
+        }
+
+        countdown(1000)
+        ",
+        );
+        let program = parser.parse().unwrap();
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let runtime = Interpreter::new().with_limits(ExecutionLimits {
+            max_call_depth: 10,
+            ..Default::default()
+        });
+
+        let result = runtime
+            .evaluate_program(&program, env)
+            .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::String("done".to_string()));
+    }
+
+    fn evaluate_with_limits(
+        source: &str,
+        limits: ExecutionLimits,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let mut parser = Parser::new(source);
+        let program = parser.parse().unwrap();
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let runtime = Interpreter::new().with_limits(limits);
+
+        runtime.evaluate_program(&program, env)
+    }
+
+    #[test]
+    fn step_limit_exceeded() {
+        let result = evaluate_with_limits(
+            "
+        func spin(n) {
+            return spin(n + 1);
+        }
+
+        spin(0)
+        ",
+            ExecutionLimits {
+                max_steps: Some(10_000),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::StepLimitExceeded(10_000)
+        );
+    }
+
+    #[test]
+    fn time_limit_exceeded() {
+        let result = evaluate_with_limits(
+            "
+        func spin(n) {
+            return spin(n + 1);
+        }
+
+        spin(0)
+        ",
+            ExecutionLimits {
+                max_duration: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::TimeLimitExceeded(Duration::from_millis(50))
+        );
+    }
+
+    #[test]
+    fn string_limit_exceeded() {
+        let result = evaluate_with_limits(
+            "
+        func grow(s) {
+            return grow(s + s);
+        }
+
+        grow('ab')
+        ",
+            ExecutionLimits {
+                max_string_bytes: Some(1024),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::StringLimitExceeded(1024)
+        );
+
+        // Checked before the string is allocated
+        for source in [
+            "let s: string = repeat('ab', 400); repeat(s, 1000000000000)",
+            "let s: string = repeat('a', 400); replace(s, 'a', repeat('b', 400))",
+            "let s: string = repeat('a', 400); regex_replace(s, 'a', '$0$0$0$0')",
+            // The strings in the returned list count too
+            "let s: string = repeat('ab', 300); chars(s)",
+            "let s: string = repeat('ab', 300); split(s, '')",
+            "let s: string = repeat('ab', 300); regex_find_all(s, '.')",
+            "let s: string = repeat('ab', 300); json_stringify([s], 0)",
+        ] {
+            let result = evaluate_with_limits(
+                source,
+                ExecutionLimits {
+                    max_string_bytes: Some(1024),
+                    ..Default::default()
+                },
+            );
+            assert_eq!(
+                result.expect_err("Failed to get error"),
+                RuntimeError::StringLimitExceeded(1024)
+            );
+        }
+    }
+
+    #[test]
+    fn reuse_interpreter_after_limit() {
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let runtime = Interpreter::new().with_limits(ExecutionLimits {
+            max_steps: Some(1_000),
+            ..Default::default()
+        });
+
+        let program = Parser::new(
+            "
+        let count: int = 1;
+        func spin(n) {
+            return spin(n + 1);
+        }
+
+        spin(0)
+        ",
+        )
+        .parse()
+        .unwrap();
+        let result = runtime.evaluate_program(&program, Rc::clone(&env));
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::StepLimitExceeded(1_000)
+        );
+
+        // The budget is renewed and the previous declarations are still there
+        let program = Parser::new("count + 1").parse().unwrap();
+        let result = runtime
+            .evaluate_program(&program, env)
+            .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(2));
+    }
+
+    #[test]
+    fn list_index() {
+        let result = evaluate(
+            "
+        let xs: list<int> = [1, 2, 3];
+        xs[0] + xs[2]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(4));
+    }
+
+    #[test]
+    fn list_assignment_is_shared() {
+        let result = evaluate(
+            "
+        func set_first(items, value) {
+            items[0] = value;
+        }
+
+        let xs: list<list<string>> = [['a'], ['b']];
+        let first: list<string> = xs[0];
+        set_first(first, 'c');
+        xs
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result,
+            RuntimeVal::new_list(vec![
+                RuntimeVal::new_list(vec![RuntimeVal::String("c".to_string())]),
+                RuntimeVal::new_list(vec![RuntimeVal::String("b".to_string())]),
+            ])
+        );
+        assert_eq!(result.to_string(), "[[c], [b]]");
+    }
+
+    #[test]
+    fn list_index_out_of_bounds() {
+        let result = evaluate("[1, 2][2]");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::IndexOutOfBounds { index: 2, len: 2 }
+        );
+
+        let result = evaluate("let xs: list<int> = [1]; xs[0 - 1] = 2;");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::IndexOutOfBounds { index: -1, len: 1 }
+        );
+    }
+
+    #[test]
+    fn index_assignment_invalid_type() {
+        for source in [
+            "let xs: list<int> = [1, 2]; xs[0] = 'a';",
+            "let grid: list<list<int>> = [[1]]; grid[0][0] = 'a';",
+            "let grid: list<list<int>> = [[1]]; grid[0] = ['a'];",
+            "let m: map<string, int> = { 'a': 1 }; m['b'] = 'b';",
+            "let m: map<string, int> = { 'a': 1 }; m[1] = 1;",
+            "let xs: list<int> = [1, 2]; xs = ['a'];",
+        ] {
+            assert_eq!(
+                evaluate(source).expect_err("Failed to get error"),
+                RuntimeError::InvalidType
+            );
+        }
+
+        let result = evaluate(
+            "
+        let grid: list<list<int>> = [[1]];
+        grid[0][0] = 2;
+        grid[0] = [3, 4];
+        grid
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[[3, 4]]");
+    }
+
+    #[test]
+    fn cyclic_values() {
+        let result = evaluate("let xs: list<list<int>> = [[1]]; xs[0] = xs;");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::CyclicValue("list".to_string())
+        );
+
+        // Indirect cycles through another container are rejected as well
+        let result = evaluate(
+            "
+        let a: list<int> = [1];
+        let m: map<string, list<int>> = { 'a': a };
+        let b: list<map<string, list<int>>> = [m];
+        m['b'] = b;
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::CyclicValue("map".to_string())
+        );
+
+        // Sharing a value without a cycle is fine
+        let result = evaluate(
+            "
+        let a: list<int> = [1];
+        let b: list<list<int>> = [a, a];
+        b[1] = a;
+        b
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[[1], [1]]");
+    }
+
+    #[test]
+    fn list_declaration_invalid_element_type() {
+        let result = evaluate("let xs: list<int> = [1, 2.5];");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::InvalidType
+        );
+    }
+
+    #[test]
+    fn map_lookup_and_insertion() {
+        let result = evaluate(
+            "
+        let m: map<string, int> = { 'b': 1, 'a': 2 };
+        m['c'] = m['a'] + m['b'];
+        m['b'] = 10;
+        m
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "{b: 10, a: 2, c: 3}");
+    }
+
+    #[test]
+    fn map_undefined_key() {
+        let result = evaluate("{ 1: 'one', true: 'yes' }[2]");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::UndefinedKey("2".to_string())
+        );
+    }
+
+    #[test]
+    fn map_invalid_key() {
+        let result = evaluate("{ 1.5: 'one' }");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::InvalidMapKey("float".to_string())
+        );
+
+        let result = evaluate(
+            "
+        let m: map<int, int> = { 1: 1 };
+        m[[1]] = 2;
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::InvalidMapKey("list".to_string())
+        );
+    }
+
+    #[test]
+    fn map_declaration_invalid_type() {
+        let result = evaluate("let m: map<string, int> = { 'a': 'b' };");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::InvalidType
+        );
+    }
+
+    #[test]
+    fn struct_fields() {
+        let result = evaluate(
+            "
+        struct Point { x: float, y: float }
+
+        let p: Point = Point { y: 2.0, x: 1.0 };
+        p.x = p.x + p.y;
+        p
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "Point { x: 3, y: 2 }");
+    }
+
+    #[test]
+    fn struct_field_types_are_checked() {
+        let result = evaluate(
+            "
+        struct Point { x: float, y: float }
+        Point { x: 1, y: 2.0 }
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::InvalidType
+        );
+
+        let result = evaluate(
+            "
+        struct Named { name: string }
+        let n: Named = Named { name: 'a' };
+        n.name = 1;
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::InvalidType
+        );
+    }
+
+    #[test]
+    fn struct_as_declared_type() {
+        let result = evaluate(
+            "
+        struct A { value: int }
+        struct B { value: int, inner: A }
+
+        let b: B = B { value: 1, inner: A { value: 2 } };
+        let a: A = b;
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::InvalidType
+        );
+    }
+
+    #[test]
+    fn struct_missing_and_undefined_fields() {
+        let result = evaluate(
+            "
+        struct Point { x: float, y: float }
+        Point { x: 1.0 }
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::MissingField("y".to_string())
+        );
+
+        let result = evaluate(
+            "
+        struct Point { x: float, y: float }
+        let p: Point = Point { x: 1.0, y: 2.0 };
+        p.z
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::UndefinedField("z".to_string())
+        );
+    }
+
+    #[test]
+    fn struct_methods() {
+        let result = evaluate(
+            "
+        struct Point { x: float, y: float }
+
+        impl Point {
+            func scale(self, factor) {
+                return Point { x: self.x * factor, y: self.y * factor };
+            }
+
+            func sum(self) {
+                return self.x + self.y;
+            }
+        }
+
+        func sum(a, b) {
+            return 0;
+        }
+
+        let p: Point = Point { x: 1.0, y: 2.0 };
+        p.scale(2).sum()
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Float(6.0));
+    }
+
+    #[test]
+    fn struct_method_mutates_receiver() {
+        let result = evaluate(
+            "
+        struct Counter { count: int }
+
+        impl Counter {
+            func increment(self) {
+                self.count = self.count + 1;
+            }
+        }
+
+        let c: Counter = Counter { count: 0 };
+        c.increment();
+        c.increment();
+        c.count
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(2));
+    }
+
+    #[test]
+    fn struct_method_tail_call() {
+        let result = evaluate(
+            "
+        struct Counter { count: int }
+
+        impl Counter {
+            func drain(self, n) {
+                if n == 0 {
+                    return self.count;
+                }
+                self.count = self.count + 1;
+                return self.drain(n - 1);
+            }
+        }
+
+        Counter { count: 0 }.drain(1000)
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(1000));
+    }
+
+    #[test]
+    fn undefined_method() {
+        let result = evaluate(
+            "
+        struct Point { x: float, y: float }
+
+        func len(p) {
+            return 0;
+        }
+
+        Point { x: 1.0, y: 2.0 }.len()
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::UndefinedMethod("Point.len".to_string())
+        );
+    }
+
+    #[test]
+    fn enum_match() {
+        let result = evaluate(
+            "
+        enum Shape { Circle(float), Rect(float, float), Empty }
+
+        func area(shape) {
+            return match shape {
+                Shape::Circle(r) => r * r * 3.0,
+                Shape::Rect(w, h) => w * h,
+                Shape::Empty => 0.0,
+            };
+        }
+
+        area(Shape::Circle(2.0)) + area(Shape::Rect(2.0, 3.0)) + area(Shape::Empty)
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Float(18.0));
+    }
+
+    #[test]
+    fn match_literal_and_wildcard_patterns() {
+        let result = evaluate(
+            "
+        enum Token { Number(int), Word(string) }
+
+        func describe(token) {
+            return match token {
+                Token::Number(0) => 'zero',
+                Token::Number(_) => 'number',
+                Token::Word('hello') => 'greeting',
+                other => 'word',
+            };
+        }
+
+        describe(Token::Number(0)) + describe(Token::Number(4)) + describe(Token::Word('hello')) + describe(Token::Word('x'))
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result,
+            RuntimeVal::String("zeronumbergreetingword".to_string())
+        );
+    }
+
+    #[test]
+    fn enum_methods_and_declared_type() {
+        let result = evaluate(
+            "
+        enum Light { Red, Green }
+
+        impl Light {
+            func next(self) {
+                return match self {
+                    Light::Red => Light::Green,
+                    Light::Green => Light::Red,
+                };
+            }
+        }
+
+        let light: Light = Light::Red.next();
+        light
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result,
+            RuntimeVal::Enum("Light".to_string(), "Green".to_string(), vec![])
+        );
+    }
+
+    #[test]
+    fn enum_variant_errors() {
+        let source = "enum Shape { Circle(float) }";
+        assert_eq!(
+            evaluate(&format!("{source} Shape::Square")).expect_err("Failed to get error"),
+            RuntimeError::UndefinedVariant("Shape::Square".to_string())
+        );
+        assert_eq!(
+            evaluate(&format!("{source} Shape::Circle(1.0, 2.0)"))
+                .expect_err("Failed to get error"),
+            RuntimeError::InvalidFuncCallParametersCount("Shape::Circle".to_string())
+        );
+        assert_eq!(
+            evaluate(&format!("{source} Shape::Circle('a')")).expect_err("Failed to get error"),
+            RuntimeError::InvalidType
+        );
+    }
+
+    #[test]
+    fn match_without_matching_arm() {
+        let result = evaluate(
+            "
+        match 3 {
+            1 => 'one',
+            2 => 'two',
+        }
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::NoMatch("3".to_string())
+        );
+    }
+
+    #[test]
+    fn option_and_result_propagation() {
+        let result = evaluate(
+            "
+        func first(items, count) {
+            if count == 0 {
+                return None;
+            }
+            return Some(items[0]);
+        }
+
+        func parse(text) {
+            if text == 'one' {
+                return Ok(1);
+            }
+            return Err('invalid number: ' + text);
+        }
+
+        func sum(a, b) {
+            return Ok(parse(a)? + parse(b)?);
+        }
+
+        [sum('one', 'one'), sum('one', 'two'), Some(first(['a'], 1)?)]
+        ",
+        );
+        assert_eq!(
+            result.expect("Failed to evaluate").to_string(),
+            "[Result::Ok(2), Result::Err(invalid number: two), Option::Some(a)]"
+        );
+    }
+
+    #[test]
+    fn try_outside_of_function() {
+        let result = evaluate("let x: int = None?;");
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::Propagated(RuntimeVal::Enum(
+                "Option".to_string(),
+                "None".to_string(),
+                vec![]
+            ))
+        );
+        assert_eq!(
+            evaluate("1?").expect_err("Failed to get error"),
+            RuntimeError::InvalidOperandType
+        );
+    }
+
+    #[test]
+    fn missing_return_value() {
+        let source = "
+        func log(message) {
+            print message;
+        }
+        ";
+
+        assert!(evaluate(&format!("{source} log('ok');")).is_ok());
+        assert_eq!(
+            evaluate(&format!("{source} let x: int = log('ok');"))
+                .expect_err("Failed to get error"),
+            RuntimeError::MissingReturnValue("log".to_string())
+        );
+        assert_eq!(
+            evaluate(&format!("{source} 1 + log('ok')")).expect_err("Failed to get error"),
+            RuntimeError::MissingReturnValue("log".to_string())
+        );
+    }
+
+    #[test]
+    fn catch_runtime_errors() {
+        let result = evaluate(
+            "
+        let caught: list<string> = ['', '', '', ''];
+        try {
+            1 / 0;
+        } catch (e) {
+            caught[0] = e.kind;
+            caught[1] = e.message;
+        }
+        try {
+            print missing;
+        } catch (e) {
+            caught[2] = e.kind;
+            caught[3] = e.message;
+        }
+        caught
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[DivisionByZero, division by zero, UndefinedVariable, undefined variable `missing`]"
+        );
+    }
+
+    #[test]
+    fn error_struct_is_reserved() {
+        for source in [
+            "struct Error { code: int }",
+            "func f() { struct Error { code: int } return 1; } f()",
+        ] {
+            assert_eq!(
+                evaluate(source).expect_err("Failed to get error"),
+                RuntimeError::StructRedeclaration("Error".to_string())
+            );
+        }
+
+        // Caught in a scope declaring its own structs
+        let result = evaluate(
+            "
+        func kind() {
+            struct Failure { code: int }
+            try {
+                1 / 0;
+            } catch (e) {
+                return e.kind;
+            }
+            return '';
+        }
+
+        kind()
+        ",
+        );
+        assert_eq!(result, Ok(RuntimeVal::String("DivisionByZero".to_string())));
+    }
+
+    #[test]
+    fn throw_and_finally() {
+        let result = evaluate(
+            "
+        let log: list<string> = ['', ''];
+
+        func check(value) {
+            if value < 0 {
+                throw Error { kind: 'Negative', message: 'negative value' };
+            }
+            return value;
+        }
+
+        func run(value) {
+            try {
+                return check(value);
+            } catch (e) {
+                log[0] = e.kind;
+                return 0;
+            } finally {
+                log[1] = 'done';
+            }
+        }
+
+        [run(0 - 1), run(2), log[0], log[1]]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[0, 2, Negative, done]");
+    }
+
+    #[test]
+    fn uncaught_throw() {
+        let result = evaluate(
+            "
+        try {
+            throw 'inner';
+        } finally {
+            print 'cleanup';
+        }
+        ",
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::Thrown(RuntimeVal::String("inner".to_string()))
+        );
+    }
+
+    #[test]
+    fn limits_are_not_catchable() {
+        let result = evaluate_with_limits(
+            "
+        func forever(n) {
+            return forever(n + 1) + 1;
+        }
+
+        try {
+            forever(0);
+        } catch (e) {
+            print e;
+        }
+        ",
+            ExecutionLimits {
+                max_call_depth: 10,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            result.expect_err("Failed to get error"),
+            RuntimeError::StackOverflow {
+                depth: 11,
+                function: "forever".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn integer_overflow() {
+        let max = "const max: int = 9223372036854775807;";
+        for (expr, op) in [
+            ("max + 1", "+"),
+            ("(0 - max) - 2", "-"),
+            ("max * 2", "*"),
+            ("((0 - max) - 1) / (0 - 1)", "/"),
+        ] {
+            assert_eq!(
+                evaluate(&format!("{max} {expr}")).expect_err("Failed to get error"),
+                RuntimeError::IntegerOverflow { op: op.to_string() }
+            );
+        }
+    }
+
+    #[test]
+    fn wrapping_and_saturating_add() {
+        let result = evaluate(
+            "
+        const max: int = 9223372036854775807;
+        [wrapping_add(max, 1) == (0 - max) - 1, saturating_add(max, 1) == max, wrapping_add(1, 2)]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[true, true, 3]");
+
+        assert_eq!(
+            evaluate("wrapping_add(1)").expect_err("Failed to get error"),
+            RuntimeError::InvalidFuncCallParametersCount("wrapping_add".to_string())
+        );
+        assert_eq!(
+            evaluate("saturating_add(1, 2.0)").expect_err("Failed to get error"),
+            RuntimeError::TypeMismatch {
+                expected: "int".to_string(),
+                found: "float".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn bigint_arithmetic() {
+        let result = evaluate(
+            "
+        let total: bigint = 123456789012345678901234n * 1000n + 7;
+        [total, total / 1000, total % 1000n, 0 - total, total > 9223372036854775807, 1n == 1]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[123456789012345678901234007, 123456789012345678901234, 7, -123456789012345678901234007, true, true]"
+        );
+
+        assert_eq!(
+            evaluate("1n / 0").expect_err("Failed to get error"),
+            RuntimeError::DivisionByZero
+        );
+        assert_eq!(
+            evaluate("1n + 1.5").expect_err("Failed to get error"),
+            RuntimeError::InvalidOperandType
+        );
+        assert_eq!(
+            evaluate("let x: int = 1n;").expect_err("Failed to get error"),
+            RuntimeError::InvalidType
+        );
+    }
+
+    #[test]
+    fn bigint_size_limit() {
+        // Rejected before any work is done
+        assert_eq!(
+            evaluate("3n ** 2000000").expect_err("Failed to get error"),
+            RuntimeError::IntegerOverflow {
+                op: "**".to_string()
+            }
+        );
+        assert_eq!(
+            evaluate("let x: bigint = 2n ** 32000; let y: bigint = x * x; y * x")
+                .expect_err("Failed to get error"),
+            RuntimeError::IntegerOverflow {
+                op: "*".to_string()
+            }
+        );
+        assert_eq!(
+            evaluate("let x: bigint = 2n ** 32767; let y: bigint = x * x + x * x; y + y")
+                .expect_err("Failed to get error"),
+            RuntimeError::IntegerOverflow {
+                op: "+".to_string()
+            }
+        );
+
+        let result = evaluate("[1n ** 4000000000, (0n - 1) ** 3, (2n ** 1000) % 1000]")
+            .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[1, -1, 376]");
+    }
+
+    #[test]
+    fn modulo_power_and_bitwise_operators() {
+        let result = evaluate(
+            "
+        [7 % 3, 7.5 % 2, 2 ** 10, 2 ** 3 ** 2, 2 ** (0 - 1), 2.0 ** 0.5 > 1.41, 2n ** 70,
+         6 & 3, 6 | 3, 6 ^ 3, 1 << 4, (0 - 16) >> 2, 1 + 2 << 1, 1 | 2 == 3]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[1, 1.5, 1024, 512, 0.5, true, 1180591620717411303424, 2, 7, 5, 16, -4, 6, true]"
+        );
+    }
+
+    #[test]
+    fn operator_errors() {
+        for (source, error) in [
+            ("1 % 0", RuntimeError::DivisionByZero),
+            ("1.5 % 0", RuntimeError::DivisionByZero),
+            ("1 << 64", RuntimeError::ShiftOutOfRange(64)),
+            ("1 >> (0 - 1)", RuntimeError::ShiftOutOfRange(-1)),
+            (
+                "10 ** 19",
+                RuntimeError::IntegerOverflow {
+                    op: "**".to_string(),
+                },
+            ),
+            ("1.5 & 1", RuntimeError::InvalidOperandType),
+        ] {
+            assert_eq!(evaluate(source).expect_err("Failed to get error"), error);
+        }
+    }
+
+    #[test]
+    fn compound_assignment() {
+        let result = evaluate(
+            "
+        let x: int = 10;
+        x += 5;
+        x -= 3;
+        x *= 4;
+        x /= 6;
+        x %= 5;
+        let items: list<int> = [1, 2];
+        items[1] *= 10;
+        [x, items[1]]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[3, 20]");
+    }
+
+    #[test]
+    fn compound_assignment_evaluates_target_once() {
+        let result = evaluate(
+            "
+        struct Point { x: int }
+
+        func next(calls) {
+            calls[0] += 1;
+            return calls[0];
+        }
+
+        func point(calls, p) {
+            calls[0] += 1;
+            return p;
+        }
+
+        let calls: list<int> = [0];
+        let items: list<int> = [1, 2, 3];
+        items[next(calls)] += 10;
+        let p: Point = Point { x: 1 };
+        point(calls, p).x += 5;
+        [items, p.x, calls[0]]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[[1, 12, 3], 6, 2]");
+    }
+
+    fn evaluate_with_policy(
+        source: &str,
+        policy: NumericPolicy,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        let mut parser = Parser::new(source);
+        let program = parser.parse().unwrap();
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let runtime = Interpreter::new().with_numeric_policy(policy);
+
+        runtime.evaluate_program(&program, env)
+    }
+
+    #[test]
+    fn int_division_by_zero_under_every_policy() {
+        for policy in [NumericPolicy::Strict, NumericPolicy::Ieee] {
+            for source in ["1 / 0", "1 % 0"] {
+                assert_eq!(
+                    evaluate_with_policy(source, policy).expect_err("Failed to get error"),
+                    RuntimeError::DivisionByZero
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn strict_float_division_by_zero() {
+        for source in [
+            "1 / 0.0",
+            "1.0 / 0",
+            "1.0 / 0.0",
+            "1 % 0.0",
+            "1.0 % 0",
+            "1.0 % 0.0",
+        ] {
+            assert_eq!(
+                evaluate_with_policy(source, NumericPolicy::Strict)
+                    .expect_err("Failed to get error"),
+                RuntimeError::DivisionByZero,
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn strict_non_finite_results() {
+        for (expr, op) in [
+            ("big * 10", "*"),
+            ("10 * big", "*"),
+            ("big * big", "*"),
+            ("big + big", "+"),
+            ("0 - big - big", "-"),
+            ("(0.0 - 1) ** 0.5", "**"),
+        ] {
+            let source = format!("const big: float = 10.0 ** 308; {expr}");
+            assert_eq!(
+                evaluate_with_policy(&source, NumericPolicy::Strict)
+                    .expect_err("Failed to get error"),
+                RuntimeError::NonFiniteResult { op: op.to_string() },
+                "{expr}"
+            );
+            assert!(evaluate_with_policy(&source, NumericPolicy::Ieee).is_ok());
+        }
+    }
+
+    #[test]
+    fn ieee_float_division_by_zero() {
+        for (source, expected) in [
+            ("1 / 0.0", "[false, false]"),
+            ("1.0 / 0", "[false, false]"),
+            ("0.0 / 0.0", "[true, false]"),
+            ("1 % 0.0", "[true, false]"),
+            ("1.0 % 0", "[true, false]"),
+            ("1.0 % 0.0", "[true, false]"),
+            ("1.0 / 2", "[false, true]"),
+        ] {
+            let result = evaluate_with_policy(
+                &format!("let x: float = {source}; [is_nan(x), is_finite(x)]"),
+                NumericPolicy::Ieee,
+            )
+            .expect("Failed to evaluate");
+            assert_eq!(result.to_string(), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn is_nan_and_is_finite_with_ints() {
+        let result =
+            evaluate("[is_nan(1), is_finite(1), is_finite(1.5)]").expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[false, true, true]");
+        assert_eq!(
+            evaluate("is_nan('a')").expect_err("Failed to get error"),
+            RuntimeError::TypeMismatch {
+                expected: "float".to_string(),
+                found: "string".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn native_functions() {
+        let mut parser = Parser::new(
+            "
+        func total(items) {
+            return double(len(items));
+        }
+
+        [total([1, 2, 3]), len('héllo'), len({ 'a': 1 }), now() > 0]
+        ",
+        );
+        let program = parser.parse().unwrap();
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        env.borrow_mut()
+            .register_native("double", 1, |args| match &args[0] {
+                RuntimeVal::Int(val) => Ok(RuntimeVal::Int(val * 2)),
+                _ => Err(RuntimeError::InvalidOperandType),
+            });
+
+        let result = Interpreter::new()
+            .evaluate_program(&program, env)
+            .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[6, 5, 1, true]");
+    }
+
+    #[test]
+    fn native_function_arity_and_override() {
+        assert_eq!(
+            evaluate("len([1], [2])").expect_err("Failed to get error"),
+            RuntimeError::InvalidFuncCallParametersCount("len".to_string())
+        );
+
+        let result = evaluate(
+            "
+        func len(items) {
+            return 42;
+        }
+        len([])
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Int(42));
+    }
+
+    #[test]
+    fn math_module() {
+        let result = evaluate(
+            "
+        [
+            math::abs(0 - 3), math::abs(0.0 - 1.5), math::min(1, 2), math::max(1, 2.5),
+            math::clamp(15, 0, 10), math::clamp(0.5, 1, 2), math::pow(2, 10),
+            math::pow(2, 0 - 1), math::sqrt(16), math::exp(0), math::PI > 3.14
+        ]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[3, 1.5, 1, 2.5, 10, 1, 1024, 0.5, 4, 1, true]"
+        );
+
+        // Rounding keeps ints as ints and floats as floats
+        let result =
+            evaluate("[math::floor(2.7), math::ceil(2.1), math::round(2.5), math::round(2)]")
+                .expect("Failed to evaluate");
+        assert_eq!(
+            result,
+            RuntimeVal::new_list(vec![
+                RuntimeVal::Float(2.0),
+                RuntimeVal::Float(3.0),
+                RuntimeVal::Float(3.0),
+                RuntimeVal::Int(2),
+            ])
+        );
+
+        assert_eq!(
+            evaluate("math::sqrt(\"4\")").expect_err("Failed to get error"),
+            RuntimeError::TypeMismatch {
+                expected: "float".to_string(),
+                found: "string".to_string()
+            }
+        );
+        assert_eq!(
+            evaluate("math::pow(2, 64)").expect_err("Failed to get error"),
+            RuntimeError::IntegerOverflow {
+                op: "**".to_string()
+            }
+        );
+        assert_eq!(
+            evaluate("math::abs(0 - 9223372036854775807 - 1)").expect_err("Failed to get error"),
+            RuntimeError::IntegerOverflow {
+                op: "math::abs".to_string()
+            }
+        );
+        assert_eq!(
+            evaluate("math::cbrt(8)").expect_err("Failed to get error"),
+            RuntimeError::UndefinedEnum("math".to_string())
+        );
+    }
+
+    #[test]
+    fn math_module_numeric_policy() {
+        for (source, op) in [
+            ("math::sqrt(0 - 1)", "math::sqrt"),
+            ("math::log(0)", "math::log"),
+            ("math::exp(1000)", "math::exp"),
+            ("math::pow(0.0 - 1, 0.5)", "**"),
+            ("math::pow(10.0, 400)", "**"),
+        ] {
+            assert_eq!(
+                evaluate_with_policy(source, NumericPolicy::Strict)
+                    .expect_err("Failed to get error"),
+                RuntimeError::NonFiniteResult { op: op.to_string() },
+                "{source}"
+            );
+        }
+
+        let result = evaluate_with_policy(
+            "[is_nan(math::sqrt(0 - 1)), math::log(0) < 0, math::pow(10.0, 400) > 0]",
+            NumericPolicy::Ieee,
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "[true, true, true]");
+    }
+
+    #[test]
+    fn string_functions() {
+        let result = evaluate(
+            "
+        [
+            upper(\"héllo\"), lower(\"ABC\"), trim(\"  a b  \"), split(\"a,b,,c\", \",\"),
+            join([\"a\", \"b\"], \"-\"), replace(\"aXbX\", \"X\", \"y\"), repeat(\"ab\", 3),
+            chars(\"né\"), contains(\"hello\", \"ell\"), starts_with(\"hello\", \"he\"),
+            ends_with(\"hello\", \"lo\"), index_of(\"héllo\", \"l\"), index_of(\"a\", \"b\"),
+            substring(\"héllo\", 1, 4), len(\"héllo\")
+        ]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[HÉLLO, abc, a b, [a, b, , c], a-b, ayby, ababab, [n, é], true, true, true, \
+             Option::Some(2), Option::None, éll, 5]"
+        );
+
+        let result =
+            evaluate("[parse_int(\"-42\") + 1, parse_float(\"2.5\")]").expect("Failed to evaluate");
+        assert_eq!(
+            result,
+            RuntimeVal::new_list(vec![RuntimeVal::Int(-41), RuntimeVal::Float(2.5)])
+        );
+
+        assert_eq!(
+            evaluate("parse_int(\"4x\")").expect_err("Failed to get error"),
+            RuntimeError::InvalidConversion("cannot parse \"4x\" as int".to_string())
+        );
+        assert_eq!(
+            evaluate("repeat(\"ab\", 9223372036854775807)").expect_err("Failed to get error"),
+            RuntimeError::InvalidConversion(
+                "cannot repeat a string 9223372036854775807 times".to_string()
+            )
+        );
+        assert_eq!(
+            evaluate("substring(\"héllo\", 2, 6)").expect_err("Failed to get error"),
+            RuntimeError::IndexOutOfBounds { index: 6, len: 5 }
+        );
+        assert_eq!(
+            evaluate("upper(1)").expect_err("Failed to get error"),
+            RuntimeError::TypeMismatch {
+                expected: "string".to_string(),
+                found: "int".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        let result = evaluate(
+            "
+        [
+            str(1) + str(2.5) + str(true), int(\"-12\"), int(2.9), int(0.0 - 2.9), int(true),
+            float(\"1e3\"), float(2), bool(\"false\"), bool(0), bool(3), str([1, \"a\"])
+        ]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[12.5true, -12, 2, -2, 1, 1000, 2, false, false, true, [1, a]]"
+        );
+        let result = evaluate("float(2)").expect("Failed to evaluate");
+        assert_eq!(result, RuntimeVal::Float(2.0));
+
+        assert_eq!(
+            evaluate("int(\"2.5\")").expect_err("Failed to get error"),
+            RuntimeError::InvalidConversion("cannot parse \"2.5\" as int".to_string())
+        );
+        assert_eq!(
+            evaluate("int(float(\"1e30\"))").expect_err("Failed to get error"),
+            RuntimeError::InvalidConversion(
+                "1000000000000000000000000000000 is out of the range of int".to_string()
+            )
+        );
+        assert_eq!(
+            evaluate("bool(\"yes\")").expect_err("Failed to get error"),
+            RuntimeError::InvalidConversion("cannot parse \"yes\" as bool".to_string())
+        );
+        assert!(matches!(
+            evaluate("float(10n ** 400)"),
+            Err(RuntimeError::InvalidConversion(message))
+                if message.ends_with("is out of the range of float")
+        ));
+        assert_eq!(
+            evaluate("float(\"1e400\")").expect_err("Failed to get error"),
+            RuntimeError::InvalidConversion("cannot parse \"1e400\" as float".to_string())
+        );
+        assert_eq!(
+            evaluate("float([1])").expect_err("Failed to get error"),
+            RuntimeError::InvalidConversion("cannot convert a list value to float".to_string())
+        );
+    }
+
+    #[test]
+    fn type_of() {
+        let result = evaluate(
+            "
+        struct Point { x: int }
+        enum Shape { Circle(float) }
+
+        [
+            type_of(1), type_of(1n), type_of(1.5), type_of(\"a\"),
+            type_of(false), type_of([1]), type_of({\"a\": 1}), type_of(Point { x: 1 }),
+            type_of(Shape::Circle(1.0)), type_of(Option::None)
+        ]
+        ",
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[int, bigint, float, string, bool, list, map, Point, Shape, Option]"
+        );
+    }
+
+    #[test]
+    fn regex_functions() {
+        let result = evaluate(
+            r#"
+        let date: string = "2024-03-15";
+        let captures: map<string, string> = match regex_captures(date, "(?P<year>\d+)-(?P<month>\d+)") {
+            Option::Some(captures) => captures,
+            Option::None => {},
+        };
+
+        [
+            regex_match(date, "^\d{4}-"), regex_match("abc", "\d"),
+            regex_find_all("a1 b22 c333", "\d+"),
+            regex_replace(date, "(\d+)-(\d+)-(\d+)", "$3/$2/$1"),
+            captures, regex_captures("abc", "(?P<digit>\d)")
+        ]
+        "#,
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[true, false, [1, 22, 333], 15/03/2024, {year: 2024, month: 03}, Option::None]"
+        );
+
+        match evaluate(r#"regex_match("a", "(")"#).expect_err("Failed to get error") {
+            RuntimeError::InvalidRegex(message) => {
+                assert!(message.contains("unclosed group"), "{message}")
+            }
+            err => panic!("Expected an invalid regex error, got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn json_functions() {
+        let result = evaluate(
+            r#"
+        let data: map<string, list<int>> = json_parse('{"b": [1, 2], "a": []}');
+        let text: string = json_stringify({"name": 'a"b', "ratio": 1.5, "none": Option::None, 1: [true]}, 0);
+        [data, data["b"][1], json_parse("[null, 18446744073709551615, -2.5]"), text]
+        "#,
+        )
+        .expect("Failed to evaluate");
+        assert_eq!(
+            result.to_string(),
+            "[{b: [1, 2], a: []}, 2, [Option::None, 18446744073709551615, -2.5], \
+             {\"name\":\"a\\\"b\",\"ratio\":1.5,\"none\":null,\"1\":[true]}]"
+        );
+
+        let result = evaluate(r#"json_stringify({"a": [1, 2]}, 2)"#).expect("Failed to evaluate");
+        assert_eq!(result.to_string(), "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+
+        let result = evaluate("json_stringify([1], 10)").expect("Failed to evaluate");
+        assert_eq!(result.to_string(), format!("[\n{}1\n]", " ".repeat(10)));
+        for (source, indent) in [("0 - 1", -1), ("11", 11)] {
+            assert_eq!(
+                evaluate(&format!("json_stringify(1, {source})")).expect_err("Failed to get error"),
+                RuntimeError::InvalidConversion(format!("invalid JSON indentation {indent}"))
+            );
+        }
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let func = NativeFunc::new("f", 0, |_| Ok(RuntimeVal::Int(1)));
+        env.borrow_mut()
+            .declare_var("f", RuntimeVal::NativeFunc(Rc::new(func)), false)
+            .unwrap();
+        let program = Parser::new("json_stringify([f], 0)").parse().unwrap();
+        assert_eq!(
+            Interpreter::new().evaluate_program(&program, env),
+            Err(RuntimeError::InvalidConversion(
+                "invalid JSON value: cannot convert a func value".to_string()
+            ))
+        );
+
+        assert_eq!(
+            evaluate("json_parse('{\"a\": 1,\n  }')").expect_err("Failed to get error"),
+            RuntimeError::InvalidJson {
+                message: "trailing comma".to_string(),
+                line: 2,
+                column: 3
+            }
+        );
+        assert_eq!(
+            evaluate("json_parse('[1] 2')").expect_err("Failed to get error"),
+            RuntimeError::InvalidJson {
+                message: "trailing characters".to_string(),
+                line: 1,
+                column: 5
+            }
+        );
+    }
 }
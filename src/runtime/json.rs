@@ -0,0 +1,108 @@
+use std::io;
+
+use serde::{Deserialize, Serialize};
+use serde_json::ser::{PrettyFormatter, Serializer};
+
+use super::{
+    convert::FromRuntimeVal,
+    environment::Environment,
+    interpreter::Interpreter,
+    values::{RuntimeError, RuntimeVal},
+};
+
+/// Register `json_parse` and `json_stringify`, values are shaped the same way
+/// as with `to_value`, e.g. `null` is `Option::None`.
+pub fn register(env: &mut Environment) {
+    env.register_fn("json_parse", |text: String| parse(&text));
+    env.register_builtin("json_stringify", 2, |interpreter, args| {
+        let indent = i64::from_runtime_val(&args[1])?;
+        Ok(RuntimeVal::String(stringify(
+            interpreter,
+            &args[0],
+            indent,
+        )?))
+    });
+}
+
+fn parse(text: &str) -> Result<RuntimeVal, RuntimeError> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    RuntimeVal::deserialize(&mut deserializer)
+        .and_then(|val| deserializer.end().map(|_| val))
+        .map_err(|err| {
+            // The position is kept apart from the message, like `ParseError`
+            let position = format!(" at line {} column {}", err.line(), err.column());
+            let message = err.to_string();
+            RuntimeError::InvalidJson {
+                message: message
+                    .strip_suffix(&position)
+                    .unwrap_or(&message)
+                    .to_string(),
+                line: err.line(),
+                column: err.column(),
+            }
+        })
+}
+
+/// Largest indentation in spaces, the same as JavaScript's `JSON.stringify`.
+const MAX_INDENT: usize = 10;
+
+/// JSON text of a value, on a single line when `indent` is 0. Map keys must be
+/// strings or ints, and non finite floats become `null`.
+fn stringify(
+    interpreter: &Interpreter,
+    val: &RuntimeVal,
+    indent: i64,
+) -> Result<String, RuntimeError> {
+    let indent = usize::try_from(indent)
+        .ok()
+        .filter(|indent| *indent <= MAX_INDENT)
+        .ok_or_else(|| {
+            RuntimeError::InvalidConversion(format!("invalid JSON indentation {indent}"))
+        })?;
+
+    let mut output = Output {
+        interpreter,
+        text: vec![],
+        exceeded: None,
+    };
+    let result = if indent == 0 {
+        val.serialize(&mut Serializer::new(&mut output))
+    } else {
+        let spaces = " ".repeat(indent);
+        let formatter = PrettyFormatter::with_indent(spaces.as_bytes());
+        val.serialize(&mut Serializer::with_formatter(&mut output, formatter))
+    };
+    if let Some(err) = output.exceeded {
+        return Err(err);
+    }
+    result.map_err(|err| RuntimeError::InvalidConversion(format!("invalid JSON value: {err}")))?;
+
+    Ok(String::from_utf8(output.text).expect("Invalid UTF-8 in JSON text"))
+}
+
+/// JSON text checked against the string limit as it is written, indentation
+/// can make it much longer than the value it comes from.
+struct Output<'a> {
+    interpreter: &'a Interpreter,
+    text: Vec<u8>,
+    exceeded: Option<RuntimeError>,
+}
+
+impl io::Write for Output<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Err(err) = self
+            .interpreter
+            .check_string_budget(self.text.len() + buf.len())
+        {
+            self.exceeded = Some(err);
+            return Err(io::Error::other("string limit exceeded"));
+        }
+
+        self.text.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
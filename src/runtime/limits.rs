@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/// Default maximum number of nested function calls.
+///
+/// The interpreter grows its stack on the heap when it runs low, so the limit
+/// bounds the memory used by runaway recursion rather than protecting the
+/// stack of the host thread. Tail calls don't count toward it.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 200;
+
+/// Resources a program is allowed to use, enforced by the `Interpreter`.
+///
+/// Usage is counted per `evaluate_program` call. When a limit is exceeded the
+/// evaluation stops with the matching `RuntimeError`, everything evaluated up to
+/// that point is kept in the environment: the host can either run another
+/// program with the same interpreter and environment, or drop both.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionLimits {
+    /// Nested function calls before `RuntimeError::StackOverflow`
+    pub max_call_depth: usize,
+    /// Statements and expressions evaluated before `RuntimeError::StepLimitExceeded`
+    pub max_steps: Option<u64>,
+    /// Wall-clock time before `RuntimeError::TimeLimitExceeded`
+    pub max_duration: Option<Duration>,
+    /// Total bytes of created strings before `RuntimeError::StringLimitExceeded`
+    pub max_string_bytes: Option<usize>,
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        ExecutionLimits {
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            max_steps: None,
+            max_duration: None,
+            max_string_bytes: None,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use super::values::{RuntimeError, RuntimeVal};
+
+/// Values usable as map keys, only types with a sound equality can be hashed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Int(i64),
+    String(String),
+    Bool(bool),
+}
+
+impl TryFrom<RuntimeVal> for MapKey {
+    type Error = RuntimeError;
+
+    fn try_from(val: RuntimeVal) -> Result<Self, Self::Error> {
+        match val {
+            RuntimeVal::Int(val) => Ok(MapKey::Int(val)),
+            RuntimeVal::String(val) => Ok(MapKey::String(val)),
+            RuntimeVal::Bool(val) => Ok(MapKey::Bool(val)),
+            val => Err(RuntimeError::InvalidMapKey(val.type_name().to_string())),
+        }
+    }
+}
+
+impl From<&MapKey> for RuntimeVal {
+    fn from(key: &MapKey) -> Self {
+        match key {
+            MapKey::Int(val) => RuntimeVal::Int(*val),
+            MapKey::String(val) => RuntimeVal::String(val.clone()),
+            MapKey::Bool(val) => RuntimeVal::Bool(*val),
+        }
+    }
+}
+
+/// Map preserving the insertion order of its keys, so iterating and printing
+/// a map is deterministic.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Map {
+    entries: Vec<(MapKey, RuntimeVal)>,
+    positions: HashMap<MapKey, usize>,
+}
+
+impl Map {
+    pub fn get(&self, key: &MapKey) -> Option<&RuntimeVal> {
+        self.positions
+            .get(key)
+            .map(|position| &self.entries[*position].1)
+    }
+
+    /// Insert or replace a value, a replaced key keeps its original position.
+    pub fn insert(&mut self, key: MapKey, val: RuntimeVal) {
+        match self.positions.get(&key) {
+            Some(position) => self.entries[*position].1 = val,
+            None => {
+                self.positions.insert(key.clone(), self.entries.len());
+                self.entries.push((key, val));
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(MapKey, RuntimeVal)> {
+        self.entries.iter()
+    }
+}
@@ -0,0 +1,147 @@
+use std::f64::consts;
+
+use crate::lexer::TokenType;
+
+use super::{
+    convert::{FromRuntimeVal, IntoRuntimeVal},
+    environment::Environment,
+    operators::{self, NumericPolicy},
+    values::{RuntimeError, RuntimeVal},
+};
+
+/// Int or float argument, functions keep ints as ints when every numeric
+/// argument is an int and give a float otherwise.
+#[derive(Clone, Copy)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn to_f64(self) -> f64 {
+        match self {
+            Number::Int(val) => val as f64,
+            Number::Float(val) => val,
+        }
+    }
+}
+
+impl FromRuntimeVal for Number {
+    fn from_runtime_val(val: &RuntimeVal) -> Result<Self, RuntimeError> {
+        match val {
+            RuntimeVal::Int(val) => Ok(Number::Int(*val)),
+            RuntimeVal::Float(val) => Ok(Number::Float(*val)),
+            val => Err(RuntimeError::TypeMismatch {
+                expected: "int or float".to_string(),
+                found: val.type_name().to_string(),
+            }),
+        }
+    }
+}
+
+impl IntoRuntimeVal for Number {
+    fn into_runtime_val(self) -> RuntimeVal {
+        match self {
+            Number::Int(val) => RuntimeVal::Int(val),
+            Number::Float(val) => RuntimeVal::Float(val),
+        }
+    }
+}
+
+/// Register the `math` module, its members are used as `math::sqrt(2.0)`.
+///
+/// Float results follow the numeric policy of the interpreter like operators,
+/// e.g. `math::sqrt(0 - 1)` raises `NonFiniteResult` under the strict policy
+/// and is NaN, which scripts can check with `is_nan`, under the IEEE one.
+pub fn register(env: &mut Environment) {
+    env.declare_var("math::PI", RuntimeVal::Float(consts::PI), true)
+        .expect("Failed to initialize math module");
+    env.declare_var("math::E", RuntimeVal::Float(consts::E), true)
+        .expect("Failed to initialize math module");
+
+    env.register_fn("math::abs", |x: Number| match x {
+        Number::Int(val) => {
+            val.checked_abs()
+                .map(Number::Int)
+                .ok_or_else(|| RuntimeError::IntegerOverflow {
+                    op: "math::abs".to_string(),
+                })
+        }
+        Number::Float(val) => Ok(Number::Float(val.abs())),
+    });
+    env.register_fn("math::min", |a: Number, b: Number| {
+        Ok(match (a, b) {
+            (Number::Int(a), Number::Int(b)) => Number::Int(a.min(b)),
+            (a, b) => Number::Float(a.to_f64().min(b.to_f64())),
+        })
+    });
+    env.register_fn("math::max", |a: Number, b: Number| {
+        Ok(match (a, b) {
+            (Number::Int(a), Number::Int(b)) => Number::Int(a.max(b)),
+            (a, b) => Number::Float(a.to_f64().max(b.to_f64())),
+        })
+    });
+    env.register_fn("math::clamp", |x: Number, low: Number, high: Number| {
+        Ok(match (x, low, high) {
+            (Number::Int(x), Number::Int(low), Number::Int(high)) => {
+                Number::Int(clamp(x, low, high))
+            }
+            (x, low, high) => Number::Float(clamp(x.to_f64(), low.to_f64(), high.to_f64())),
+        })
+    });
+
+    // Ints are already whole numbers
+    env.register_fn("math::floor", |x: Number| Ok(rounded(x, f64::floor)));
+    env.register_fn("math::ceil", |x: Number| Ok(rounded(x, f64::ceil)));
+    env.register_fn("math::round", |x: Number| Ok(rounded(x, f64::round)));
+
+    // Same as the `**` operator, ints stay ints and overflows are errors
+    env.register_builtin("math::pow", 2, |interpreter, args| {
+        let base = Number::from_runtime_val(&args[0])?;
+        let exponent = Number::from_runtime_val(&args[1])?;
+        operators::binary_op(
+            base.into_runtime_val(),
+            &TokenType::StarStar,
+            exponent.into_runtime_val(),
+            interpreter.numeric_policy(),
+        )
+    });
+
+    register_float_fn(env, "math::sqrt", f64::sqrt);
+    register_float_fn(env, "math::sin", f64::sin);
+    register_float_fn(env, "math::cos", f64::cos);
+    register_float_fn(env, "math::tan", f64::tan);
+    register_float_fn(env, "math::log", f64::ln);
+    register_float_fn(env, "math::exp", f64::exp);
+}
+
+/// A non finite result is checked the same way as for a float operator.
+fn register_float_fn(env: &mut Environment, name: &'static str, func: fn(f64) -> f64) {
+    env.register_builtin(name, 1, move |interpreter, args| {
+        let result = func(f64::from_runtime_val(&args[0])?);
+        if interpreter.numeric_policy() == NumericPolicy::Strict && !result.is_finite() {
+            return Err(RuntimeError::NonFiniteResult {
+                op: name.to_string(),
+            });
+        }
+
+        Ok(RuntimeVal::Float(result))
+    });
+}
+
+fn rounded(x: Number, round: fn(f64) -> f64) -> Number {
+    match x {
+        Number::Int(val) => Number::Int(val),
+        Number::Float(val) => Number::Float(round(val)),
+    }
+}
+
+/// Unlike `Ord::clamp` this doesn't panic when `low > high`, `high` wins.
+fn clamp<T: PartialOrd>(x: T, low: T, high: T) -> T {
+    let x = if x < low { low } else { x };
+    if x > high {
+        high
+    } else {
+        x
+    }
+}
@@ -1,3 +1,17 @@
+pub mod builtins;
+pub mod convert;
+pub mod convert_fns;
+pub mod enums;
 pub mod environment;
 pub mod interpreter;
+pub mod json;
+pub mod limits;
+pub mod map;
+pub mod math;
+pub mod native;
+pub mod operators;
+pub mod patterns;
+pub mod serialization;
+pub mod strings;
+pub mod structs;
 pub mod values;
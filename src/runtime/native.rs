@@ -0,0 +1,72 @@
+use std::fmt::Debug;
+
+use super::{
+    interpreter::Interpreter,
+    values::{RuntimeError, RuntimeVal},
+};
+
+pub type NativeFn = dyn Fn(&[RuntimeVal]) -> Result<RuntimeVal, RuntimeError>;
+
+/// Builtin that also depends on the interpreter calling it, e.g. on its
+/// numeric policy or limits.
+pub type BuiltinFn = dyn Fn(&Interpreter, &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError>;
+
+/// Function implemented in Rust by the host and callable from scripts.
+pub struct NativeFunc {
+    pub name: String,
+    pub arity: usize,
+    func: Box<BuiltinFn>,
+}
+
+impl NativeFunc {
+    pub fn new(
+        name: &str,
+        arity: usize,
+        func: impl Fn(&[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> + 'static,
+    ) -> Self {
+        Self::builtin(name, arity, move |_, args| func(args))
+    }
+
+    pub fn builtin(
+        name: &str,
+        arity: usize,
+        func: impl Fn(&Interpreter, &[RuntimeVal]) -> Result<RuntimeVal, RuntimeError> + 'static,
+    ) -> Self {
+        NativeFunc {
+            name: name.to_string(),
+            arity,
+            func: Box::new(func),
+        }
+    }
+
+    /// Call the function, the number of arguments is checked against its arity.
+    pub fn call(
+        &self,
+        interpreter: &Interpreter,
+        args: &[RuntimeVal],
+    ) -> Result<RuntimeVal, RuntimeError> {
+        if args.len() != self.arity {
+            return Err(RuntimeError::InvalidFuncCallParametersCount(
+                self.name.clone(),
+            ));
+        }
+
+        (self.func)(interpreter, args)
+    }
+}
+
+impl Debug for NativeFunc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunc")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+// Closures can't be compared, native functions are compared by identity
+impl PartialEq for NativeFunc {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
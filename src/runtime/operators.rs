@@ -0,0 +1,255 @@
+use std::str::FromStr;
+
+use crate::{bigint::BigInt, lexer::TokenType};
+
+use super::values::{RuntimeError, RuntimeVal};
+
+/// How float arithmetic handles results that aren't a finite number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumericPolicy {
+    /// Dividing by zero raises `DivisionByZero` and any other operation
+    /// producing an infinite or NaN value raises `NonFiniteResult`.
+    #[default]
+    Strict,
+    /// IEEE 754 semantics, `inf` and `NaN` are regular values.
+    Ieee,
+}
+
+impl FromStr for NumericPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "strict" => Ok(NumericPolicy::Strict),
+            "ieee" => Ok(NumericPolicy::Ieee),
+            _ => Err(format!("Unknown numeric policy: {s}")),
+        }
+    }
+}
+
+/// Binary operator applied by a compound assignment, e.g. `+` for `+=`.
+pub fn compound_op(op: &TokenType) -> Option<TokenType> {
+    match op {
+        TokenType::PlusEqual => Some(TokenType::Plus),
+        TokenType::MinusEqual => Some(TokenType::Minus),
+        TokenType::StarEqual => Some(TokenType::Star),
+        TokenType::SlashEqual => Some(TokenType::Slash),
+        TokenType::PercentEqual => Some(TokenType::Percent),
+        _ => None,
+    }
+}
+
+/// Apply a binary operator to two already evaluated operands.
+///
+/// This is shared between the interpreter and the optimizer so that constant
+/// folding always produces the same result (or error) as the runtime would.
+/// Integer division by zero is an error whatever the policy, ints have no
+/// infinite value.
+pub fn binary_op(
+    left: RuntimeVal,
+    op: &TokenType,
+    right: RuntimeVal,
+    policy: NumericPolicy,
+) -> Result<RuntimeVal, RuntimeError> {
+    match left {
+        RuntimeVal::Int(left) => match right {
+            RuntimeVal::Int(right) => match op {
+                TokenType::Plus => checked(left.checked_add(right), "+"),
+                TokenType::Minus => checked(left.checked_sub(right), "-"),
+                TokenType::Star => checked(left.checked_mul(right), "*"),
+                TokenType::Slash => {
+                    if right == 0 {
+                        return Err(RuntimeError::DivisionByZero);
+                    }
+                    // i64::MIN / -1 doesn't fit either
+                    checked(left.checked_div(right), "/")
+                }
+                TokenType::Percent => {
+                    if right == 0 {
+                        return Err(RuntimeError::DivisionByZero);
+                    }
+                    checked(left.checked_rem(right), "%")
+                }
+                TokenType::StarStar => int_pow(left, right),
+                TokenType::Ampersand => Ok(RuntimeVal::Int(left & right)),
+                TokenType::Pipe => Ok(RuntimeVal::Int(left | right)),
+                TokenType::Caret => Ok(RuntimeVal::Int(left ^ right)),
+                TokenType::LessLess | TokenType::GreaterGreater => shift(left, op, right),
+                _ => compare(left, op, right),
+            },
+            // An int is promoted when mixed with a bigint
+            RuntimeVal::BigInt(right) => bigint_op(BigInt::from(left), op, right),
+            RuntimeVal::Float(right) => float_op(left as f64, op, right, policy),
+            _ => Err(RuntimeError::InvalidOperandType),
+        },
+        RuntimeVal::BigInt(left) => match right {
+            RuntimeVal::Int(right) if *op == TokenType::StarStar => bigint_pow(left, right),
+            RuntimeVal::BigInt(right) => bigint_op(left, op, right),
+            RuntimeVal::Int(right) => bigint_op(left, op, BigInt::from(right)),
+            _ => Err(RuntimeError::InvalidOperandType),
+        },
+        RuntimeVal::Float(left) => match right {
+            RuntimeVal::Int(right) => float_op(left, op, right as f64, policy),
+            RuntimeVal::Float(right) => float_op(left, op, right, policy),
+            _ => Err(RuntimeError::InvalidOperandType),
+        },
+        RuntimeVal::String(left) => match right {
+            RuntimeVal::String(right) => match op {
+                TokenType::Plus => Ok(RuntimeVal::String(left + &right)),
+                _ => compare(left, op, right),
+            },
+            _ => Err(RuntimeError::InvalidOperandType),
+        },
+        RuntimeVal::Bool(left) => match right {
+            RuntimeVal::Bool(right) => match op {
+                TokenType::EqualEqual | TokenType::BangEqual => compare(left, op, right),
+                _ => Err(RuntimeError::InvalidOperandType),
+            },
+            _ => Err(RuntimeError::InvalidOperandType),
+        },
+        _ => Err(RuntimeError::InvalidOperandType),
+    }
+}
+
+/// Largest bigint magnitude in bits an operator may produce.
+///
+/// Multiplication is quadratic in the operand size and the deadline is only
+/// checked between steps, so results are limited to keep a single operator
+/// fast.
+const MAX_BIGINT_BITS: u64 = 1 << 16;
+
+fn bigint_op(left: BigInt, op: &TokenType, right: BigInt) -> Result<RuntimeVal, RuntimeError> {
+    match op {
+        TokenType::Plus => bigint_sized(left + right, "+"),
+        TokenType::Minus => bigint_sized(left - right, "-"),
+        TokenType::Star => {
+            // The product has at most as many bits as both operands together
+            if left.bits() + right.bits() > MAX_BIGINT_BITS {
+                return Err(RuntimeError::IntegerOverflow {
+                    op: "*".to_string(),
+                });
+            }
+            Ok(RuntimeVal::BigInt(left * right))
+        }
+        TokenType::Slash => left
+            .checked_div(&right)
+            .map(RuntimeVal::BigInt)
+            .ok_or(RuntimeError::DivisionByZero),
+        TokenType::Percent => left
+            .checked_rem(&right)
+            .map(RuntimeVal::BigInt)
+            .ok_or(RuntimeError::DivisionByZero),
+        _ => compare(left, op, right),
+    }
+}
+
+/// Raise a bigint to a non negative int exponent, other bigint exponents are
+/// not supported.
+fn bigint_pow(base: BigInt, exponent: i64) -> Result<RuntimeVal, RuntimeError> {
+    let exponent = u32::try_from(exponent).map_err(|_| RuntimeError::InvalidOperandType)?;
+    // Checked before computing anything, only 0, 1 and -1 have a magnitude
+    // that doesn't grow with the exponent
+    let bits = base.bits();
+    if bits > 1 && bits.saturating_mul(u64::from(exponent)) > MAX_BIGINT_BITS {
+        return Err(RuntimeError::IntegerOverflow {
+            op: "**".to_string(),
+        });
+    }
+    Ok(RuntimeVal::BigInt(base.pow(exponent)))
+}
+
+/// Sums and differences grow by a bit at most, they are only checked after.
+fn bigint_sized(value: BigInt, op: &str) -> Result<RuntimeVal, RuntimeError> {
+    if value.bits() > MAX_BIGINT_BITS {
+        return Err(RuntimeError::IntegerOverflow { op: op.to_string() });
+    }
+    Ok(RuntimeVal::BigInt(value))
+}
+
+/// Integer power, a negative exponent gives a fraction computed as a float.
+fn int_pow(base: i64, exponent: i64) -> Result<RuntimeVal, RuntimeError> {
+    if exponent < 0 {
+        if base == 0 {
+            return Err(RuntimeError::DivisionByZero);
+        }
+        return Ok(RuntimeVal::Float((base as f64).powf(exponent as f64)));
+    }
+
+    match u32::try_from(exponent) {
+        Ok(exponent) => checked(base.checked_pow(exponent), "**"),
+        // Only 0, 1 and -1 don't overflow with such large exponents
+        Err(_) => match base {
+            0 | 1 => Ok(RuntimeVal::Int(base)),
+            -1 if exponent % 2 == 0 => Ok(RuntimeVal::Int(1)),
+            -1 => Ok(RuntimeVal::Int(-1)),
+            _ => Err(RuntimeError::IntegerOverflow {
+                op: "**".to_string(),
+            }),
+        },
+    }
+}
+
+/// Shift by 0 to 63 bits, `>>` is an arithmetic shift keeping the sign.
+fn shift(left: i64, op: &TokenType, right: i64) -> Result<RuntimeVal, RuntimeError> {
+    let amount = u32::try_from(right)
+        .ok()
+        .filter(|amount| *amount < i64::BITS)
+        .ok_or(RuntimeError::ShiftOutOfRange(right))?;
+
+    match op {
+        TokenType::LessLess => Ok(RuntimeVal::Int(left << amount)),
+        _ => Ok(RuntimeVal::Int(left >> amount)),
+    }
+}
+
+/// Arithmetic on floats, an int mixed with a float is converted first.
+fn float_op(
+    left: f64,
+    op: &TokenType,
+    right: f64,
+    policy: NumericPolicy,
+) -> Result<RuntimeVal, RuntimeError> {
+    let strict = policy == NumericPolicy::Strict;
+    let (result, symbol) = match op {
+        TokenType::Plus => (left + right, "+"),
+        TokenType::Minus => (left - right, "-"),
+        TokenType::Star => (left * right, "*"),
+        TokenType::Slash | TokenType::Percent if strict && right == 0.0 => {
+            return Err(RuntimeError::DivisionByZero)
+        }
+        TokenType::Slash => (left / right, "/"),
+        TokenType::Percent => (left % right, "%"),
+        TokenType::StarStar => (left.powf(right), "**"),
+        _ => return compare(left, op, right),
+    };
+
+    if strict && !result.is_finite() {
+        return Err(RuntimeError::NonFiniteResult {
+            op: symbol.to_string(),
+        });
+    }
+
+    Ok(RuntimeVal::Float(result))
+}
+
+/// Integer arithmetic is checked in every build mode instead of panicking in
+/// debug and wrapping in release.
+fn checked(result: Option<i64>, op: &str) -> Result<RuntimeVal, RuntimeError> {
+    result
+        .map(RuntimeVal::Int)
+        .ok_or_else(|| RuntimeError::IntegerOverflow { op: op.to_string() })
+}
+
+fn compare<T: PartialOrd>(left: T, op: &TokenType, right: T) -> Result<RuntimeVal, RuntimeError> {
+    let result = match op {
+        TokenType::EqualEqual => left == right,
+        TokenType::BangEqual => left != right,
+        TokenType::Less => left < right,
+        TokenType::LessEqual => left <= right,
+        TokenType::Greater => left > right,
+        TokenType::GreaterEqual => left >= right,
+        _ => return Err(RuntimeError::InvalidOperandType),
+    };
+
+    Ok(RuntimeVal::Bool(result))
+}
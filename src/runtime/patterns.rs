@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use super::{
+    convert::{FromRuntimeVal, IntoRuntimeVal},
+    environment::Environment,
+    interpreter::Interpreter,
+    map::{Map, MapKey},
+    values::{RuntimeError, RuntimeVal},
+};
+
+/// Patterns kept compiled at once, scripts building patterns dynamically
+/// would otherwise grow the cache without bound.
+const MAX_CACHED_PATTERNS: usize = 64;
+
+/// Compiled patterns by source, so a pattern used in a loop is only compiled
+/// once.
+#[derive(Default)]
+pub struct PatternCache {
+    patterns: HashMap<String, Regex>,
+}
+
+impl PatternCache {
+    pub fn get(&mut self, pattern: &str) -> Result<Regex, RuntimeError> {
+        if let Some(regex) = self.patterns.get(pattern) {
+            return Ok(regex.clone());
+        }
+
+        let regex =
+            Regex::new(pattern).map_err(|err| RuntimeError::InvalidRegex(err.to_string()))?;
+        if self.patterns.len() >= MAX_CACHED_PATTERNS {
+            self.patterns.clear();
+        }
+        self.patterns.insert(pattern.to_string(), regex.clone());
+
+        Ok(regex)
+    }
+}
+
+/// Register the regex functions, they take the text first and the pattern
+/// second. Compiled patterns are cached by the interpreter running the script.
+pub fn register(env: &mut Environment) {
+    env.register_builtin("regex_match", 2, |interpreter, args| {
+        let (text, regex) = text_and_regex(interpreter, args)?;
+        Ok(RuntimeVal::Bool(regex.is_match(&text)))
+    });
+
+    env.register_builtin("regex_find_all", 2, |interpreter, args| {
+        let (text, regex) = text_and_regex(interpreter, args)?;
+        let found: Vec<String> = regex
+            .find_iter(&text)
+            .map(|found| found.as_str().to_string())
+            .collect();
+        interpreter.track_string_bytes(found.iter().map(String::len).sum())?;
+
+        Ok(found.into_runtime_val())
+    });
+
+    // `$1` and `$name` in the replacement refer to groups of the pattern. The
+    // result is checked against the string limit as it grows, a replacement
+    // repeating groups can make it much longer than the text
+    env.register_builtin("regex_replace", 3, |interpreter, args| {
+        let (text, regex) = text_and_regex(interpreter, args)?;
+        let replacement = String::from_runtime_val(&args[2])?;
+
+        let mut replaced = String::new();
+        let mut end = 0;
+        for captures in regex.captures_iter(&text) {
+            // The whole match is always there
+            let found = captures.get(0).unwrap();
+            replaced.push_str(&text[end..found.start()]);
+            captures.expand(&replacement, &mut replaced);
+            end = found.end();
+
+            interpreter.check_string_budget(replaced.len())?;
+        }
+        interpreter.check_string_budget(replaced.len() + text.len() - end)?;
+        replaced.push_str(&text[end..]);
+
+        Ok(RuntimeVal::String(replaced))
+    });
+
+    // Named groups of the first match, a group that didn't participate in
+    // the match is left out of the map
+    env.register_builtin("regex_captures", 2, |interpreter, args| {
+        let (text, regex) = text_and_regex(interpreter, args)?;
+        Ok(regex
+            .captures(&text)
+            .map(|captures| {
+                let mut map = Map::default();
+                for name in regex.capture_names().flatten() {
+                    if let Some(group) = captures.name(name) {
+                        map.insert(
+                            MapKey::String(name.to_string()),
+                            RuntimeVal::String(group.as_str().to_string()),
+                        );
+                    }
+                }
+
+                RuntimeVal::new_map(map)
+            })
+            .into_runtime_val())
+    });
+}
+
+fn text_and_regex(
+    interpreter: &Interpreter,
+    args: &[RuntimeVal],
+) -> Result<(String, Regex), RuntimeError> {
+    let text = String::from_runtime_val(&args[0])?;
+    let pattern = String::from_runtime_val(&args[1])?;
+    Ok((text, interpreter.regex(&pattern)?))
+}
@@ -0,0 +1,910 @@
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+        VariantAccess, Visitor,
+    },
+    forward_to_deserialize_any,
+    ser::{self, SerializeMap as _, SerializeSeq as _},
+    Deserialize, Serialize,
+};
+
+use crate::bigint::BigInt;
+
+use super::{
+    map::{Map, MapKey},
+    values::{RuntimeError, RuntimeVal},
+};
+
+// Data is exchanged the way serde_json shapes it:
+//
+// - Rust structs and maps become script maps, sequences and tuples become lists
+// - `Option` maps to the `Option` enum, unit values to `Option::None`
+// - an enum variant without data is its name as a string, any other variant a
+//   map with the name as its single key, e.g. `{"Circle": 1.5}`
+// - integers too large for an `int` become a `bigint`
+//
+// Functions and other values without data can't be converted.
+
+/// Convert any serializable Rust value to a script value, `Engine` does it in
+/// `set_global_serde`.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<RuntimeVal, RuntimeError> {
+    value.serialize(Serializer)
+}
+
+/// Convert a script value to any deserializable Rust value.
+pub fn from_value<T: DeserializeOwned>(val: RuntimeVal) -> Result<T, RuntimeError> {
+    T::deserialize(val)
+}
+
+impl ser::Error for RuntimeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        RuntimeError::InvalidConversion(msg.to_string())
+    }
+}
+
+impl de::Error for RuntimeError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        RuntimeError::InvalidConversion(msg.to_string())
+    }
+}
+
+fn not_data(val: &RuntimeVal) -> RuntimeError {
+    RuntimeError::InvalidConversion(format!("cannot convert a {} value", val.type_name()))
+}
+
+/// Data of an enum variant: nothing, its single field or a list of its fields.
+fn variant_payload(fields: &[RuntimeVal]) -> Option<RuntimeVal> {
+    match fields {
+        [] => None,
+        [field] => Some(field.clone()),
+        fields => Some(RuntimeVal::new_list(fields.to_vec())),
+    }
+}
+
+fn variant_map(variant: &str, payload: RuntimeVal) -> RuntimeVal {
+    let mut map = Map::default();
+    map.insert(MapKey::String(variant.to_string()), payload);
+    RuntimeVal::new_map(map)
+}
+
+fn option(val: Option<RuntimeVal>) -> RuntimeVal {
+    match val {
+        Some(val) => RuntimeVal::Enum("Option".to_string(), "Some".to_string(), vec![val]),
+        None => RuntimeVal::Enum("Option".to_string(), "None".to_string(), vec![]),
+    }
+}
+
+fn int_or_bigint<T: TryInto<i64> + ToString>(val: T) -> RuntimeVal {
+    let digits = val.to_string();
+    match val.try_into() {
+        Ok(val) => RuntimeVal::Int(val),
+        Err(_) => RuntimeVal::BigInt(digits.parse().expect("Invalid integer digits")),
+    }
+}
+
+impl Serialize for RuntimeVal {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            RuntimeVal::Int(val) => serializer.serialize_i64(*val),
+            RuntimeVal::BigInt(val) => match val.to_string().parse::<i128>() {
+                Ok(val) => serializer.serialize_i128(val),
+                Err(_) => serializer.serialize_str(&val.to_string()),
+            },
+            RuntimeVal::Float(val) => serializer.serialize_f64(*val),
+            RuntimeVal::Bool(val) => serializer.serialize_bool(*val),
+            RuntimeVal::String(val) => serializer.serialize_str(val),
+            RuntimeVal::List(elements) => {
+                let elements = elements.borrow();
+                let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+                for element in elements.iter() {
+                    seq.serialize_element(element)?;
+                }
+                seq.end()
+            }
+            RuntimeVal::Map(map) => {
+                let map = map.borrow();
+                let mut entries = serializer.serialize_map(Some(map.len()))?;
+                for (key, val) in map.iter() {
+                    entries.serialize_entry(&RuntimeVal::from(key), val)?;
+                }
+                entries.end()
+            }
+            RuntimeVal::Struct(instance) => {
+                let instance = instance.borrow();
+                let mut entries = serializer.serialize_map(None)?;
+                for (field, val) in instance.iter() {
+                    entries.serialize_entry(field, val)?;
+                }
+                entries.end()
+            }
+            RuntimeVal::Enum(name, variant, fields) if name == "Option" => match fields.first() {
+                Some(val) if variant == "Some" => serializer.serialize_some(val),
+                _ => serializer.serialize_none(),
+            },
+            RuntimeVal::Enum(_, variant, fields) => match variant_payload(fields) {
+                None => serializer.serialize_str(variant),
+                Some(payload) => {
+                    let mut entries = serializer.serialize_map(Some(1))?;
+                    entries.serialize_entry(variant, &payload)?;
+                    entries.end()
+                }
+            },
+            val => Err(ser::Error::custom(not_data(val))),
+        }
+    }
+}
+
+/// Serializer producing script values, see `to_value`.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    type SerializeSeq = SerializeList;
+    type SerializeTuple = SerializeList;
+    type SerializeTupleStruct = SerializeList;
+    type SerializeTupleVariant = SerializeList;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeMap;
+
+    fn serialize_bool(self, v: bool) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Int(v.into()))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Int(v.into()))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Int(v.into()))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Int(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<RuntimeVal, RuntimeError> {
+        Ok(int_or_bigint(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Int(v.into()))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Int(v.into()))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Int(v.into()))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<RuntimeVal, RuntimeError> {
+        Ok(int_or_bigint(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<RuntimeVal, RuntimeError> {
+        Ok(int_or_bigint(v))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Float(v.into()))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::new_list(
+            v.iter()
+                .map(|byte| RuntimeVal::Int((*byte).into()))
+                .collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<RuntimeVal, RuntimeError> {
+        Ok(option(None))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<RuntimeVal, RuntimeError> {
+        Ok(option(Some(value.serialize(Serializer)?)))
+    }
+
+    fn serialize_unit(self) -> Result<RuntimeVal, RuntimeError> {
+        Ok(option(None))
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RuntimeVal, RuntimeError> {
+        Ok(option(None))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        Ok(RuntimeVal::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        value.serialize(Serializer)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<RuntimeVal, RuntimeError> {
+        Ok(variant_map(variant, value.serialize(Serializer)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SerializeList, RuntimeError> {
+        Ok(SerializeList {
+            elements: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SerializeList, RuntimeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, RuntimeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<SerializeList, RuntimeError> {
+        Ok(SerializeList {
+            elements: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap, RuntimeError> {
+        Ok(SerializeMap {
+            map: Map::default(),
+            key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SerializeMap, RuntimeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SerializeMap, RuntimeError> {
+        Ok(SerializeMap {
+            map: Map::default(),
+            key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// Sequences, tuples and tuple variants, see `Serializer`.
+pub struct SerializeList {
+    elements: Vec<RuntimeVal>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeList {
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        self.elements.push(value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RuntimeVal, RuntimeError> {
+        let list = RuntimeVal::new_list(self.elements);
+        match self.variant {
+            Some(variant) => Ok(variant_map(variant, list)),
+            None => Ok(list),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeList {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RuntimeVal, RuntimeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SerializeList {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RuntimeVal, RuntimeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeList {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RuntimeVal, RuntimeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeList {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<RuntimeVal, RuntimeError> {
+        self.finish()
+    }
+}
+
+/// Maps, structs and struct variants, see `Serializer`.
+pub struct SerializeMap {
+    map: Map,
+    // Key of the entry whose value is serialized next
+    key: Option<MapKey>,
+    variant: Option<&'static str>,
+}
+
+impl SerializeMap {
+    fn finish(self) -> Result<RuntimeVal, RuntimeError> {
+        let map = RuntimeVal::new_map(self.map);
+        match self.variant {
+            Some(variant) => Ok(variant_map(variant, map)),
+            None => Ok(map),
+        }
+    }
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), RuntimeError> {
+        self.key = Some(MapKey::try_from(key.serialize(Serializer)?)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RuntimeError> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| ser::Error::custom("map value without a key"))?;
+        self.map.insert(key, value.serialize(Serializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<RuntimeVal, RuntimeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RuntimeError> {
+        self.map.insert(
+            MapKey::String(key.to_string()),
+            value.serialize(Serializer)?,
+        );
+        Ok(())
+    }
+
+    fn end(self) -> Result<RuntimeVal, RuntimeError> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+    type Ok = RuntimeVal;
+    type Error = RuntimeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RuntimeError> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> Result<RuntimeVal, RuntimeError> {
+        self.finish()
+    }
+}
+
+impl<'de> IntoDeserializer<'de, RuntimeError> for RuntimeVal {
+    type Deserializer = RuntimeVal;
+
+    fn into_deserializer(self) -> RuntimeVal {
+        self
+    }
+}
+
+fn visit_list<'de, V: Visitor<'de>>(
+    elements: Vec<RuntimeVal>,
+    visitor: V,
+) -> Result<V::Value, RuntimeError> {
+    let mut seq = SeqDeserializer::new(elements.into_iter());
+    let value = visitor.visit_seq(&mut seq)?;
+    seq.end()?;
+    Ok(value)
+}
+
+fn visit_entries<'de, V: Visitor<'de>>(
+    entries: Vec<(RuntimeVal, RuntimeVal)>,
+    visitor: V,
+) -> Result<V::Value, RuntimeError> {
+    let mut map = MapDeserializer::new(entries.into_iter());
+    let value = visitor.visit_map(&mut map)?;
+    map.end()?;
+    Ok(value)
+}
+
+fn visit_bigint<'de, V: Visitor<'de>>(val: BigInt, visitor: V) -> Result<V::Value, RuntimeError> {
+    // Most visitors of unsigned types don't accept an i128
+    let digits = val.to_string();
+    if let Ok(val) = digits.parse::<u64>() {
+        return visitor.visit_u64(val);
+    }
+    match digits.parse::<i128>() {
+        Ok(val) => visitor.visit_i128(val),
+        Err(_) => visitor.visit_string(digits),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for RuntimeVal {
+    type Error = RuntimeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuntimeError> {
+        match self {
+            RuntimeVal::Int(val) => visitor.visit_i64(val),
+            RuntimeVal::BigInt(val) => visit_bigint(val, visitor),
+            RuntimeVal::Float(val) => visitor.visit_f64(val),
+            RuntimeVal::Bool(val) => visitor.visit_bool(val),
+            RuntimeVal::String(val) => visitor.visit_string(val),
+            RuntimeVal::List(elements) => visit_list(elements.borrow().clone(), visitor),
+            RuntimeVal::Map(map) => {
+                let entries = map
+                    .borrow()
+                    .iter()
+                    .map(|(key, val)| (RuntimeVal::from(key), val.clone()))
+                    .collect();
+                visit_entries(entries, visitor)
+            }
+            RuntimeVal::Struct(instance) => {
+                let entries = instance
+                    .borrow()
+                    .iter()
+                    .map(|(field, val)| (RuntimeVal::String(field.clone()), val.clone()))
+                    .collect();
+                visit_entries(entries, visitor)
+            }
+            RuntimeVal::Enum(name, variant, mut fields) if name == "Option" => {
+                match variant.as_str() {
+                    "Some" if fields.len() == 1 => visitor.visit_some(fields.remove(0)),
+                    _ => visitor.visit_none(),
+                }
+            }
+            RuntimeVal::Enum(_, variant, fields) => match variant_payload(&fields) {
+                None => visitor.visit_string(variant),
+                Some(payload) => {
+                    visit_entries(vec![(RuntimeVal::String(variant), payload)], visitor)
+                }
+            },
+            val => Err(not_data(&val)),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuntimeError> {
+        match self {
+            RuntimeVal::Enum(ref name, ..) if name == "Option" => self.deserialize_any(visitor),
+            val => visitor.visit_some(val),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RuntimeError> {
+        match self {
+            RuntimeVal::Enum(name, variant, _) if name == "Option" && variant == "None" => {
+                visitor.visit_unit()
+            }
+            val => val.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RuntimeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RuntimeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RuntimeError> {
+        let (variant, payload) = match self {
+            RuntimeVal::String(variant) => (variant, None),
+            RuntimeVal::Enum(_, variant, fields) => {
+                let payload = variant_payload(&fields);
+                (variant, payload)
+            }
+            RuntimeVal::Map(map) => {
+                let map = map.borrow();
+                let entry = match map.iter().next() {
+                    Some((MapKey::String(variant), payload)) if map.len() == 1 => {
+                        (variant.clone(), Some(payload.clone()))
+                    }
+                    _ => {
+                        return Err(de::Error::custom(
+                            "expected a map with a single variant name key",
+                        ))
+                    }
+                };
+                entry
+            }
+            val => return Err(not_data(&val)),
+        };
+
+        visitor.visit_enum(EnumDeserializer { variant, payload })
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Enum variant with its data, see `variant_payload`.
+struct EnumDeserializer {
+    variant: String,
+    payload: Option<RuntimeVal>,
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+    type Error = RuntimeError;
+    type Variant = VariantDeserializer;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, VariantDeserializer), RuntimeError> {
+        let variant = seed.deserialize(RuntimeVal::String(self.variant))?;
+        Ok((
+            variant,
+            VariantDeserializer {
+                payload: self.payload,
+            },
+        ))
+    }
+}
+
+struct VariantDeserializer {
+    payload: Option<RuntimeVal>,
+}
+
+impl VariantDeserializer {
+    fn payload(self) -> Result<RuntimeVal, RuntimeError> {
+        self.payload
+            .ok_or_else(|| de::Error::custom("expected variant data"))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+    type Error = RuntimeError;
+
+    fn unit_variant(self) -> Result<(), RuntimeError> {
+        match self.payload {
+            None => Ok(()),
+            Some(_) => Err(de::Error::custom("unexpected variant data")),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, RuntimeError> {
+        seed.deserialize(self.payload()?)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RuntimeError> {
+        de::Deserializer::deserialize_any(self.payload()?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RuntimeError> {
+        de::Deserializer::deserialize_any(self.payload()?, visitor)
+    }
+}
+
+/// Script values built from any self-describing format, e.g. JSON text.
+impl<'de> Deserialize<'de> for RuntimeVal {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(RuntimeValVisitor)
+    }
+}
+
+struct RuntimeValVisitor;
+
+impl<'de> Visitor<'de> for RuntimeValVisitor {
+    type Value = RuntimeVal;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a script value")
+    }
+
+    fn visit_bool<E: de::Error>(self, val: bool) -> Result<RuntimeVal, E> {
+        Ok(RuntimeVal::Bool(val))
+    }
+
+    fn visit_i64<E: de::Error>(self, val: i64) -> Result<RuntimeVal, E> {
+        Ok(RuntimeVal::Int(val))
+    }
+
+    fn visit_u64<E: de::Error>(self, val: u64) -> Result<RuntimeVal, E> {
+        Ok(int_or_bigint(val))
+    }
+
+    fn visit_i128<E: de::Error>(self, val: i128) -> Result<RuntimeVal, E> {
+        Ok(int_or_bigint(val))
+    }
+
+    fn visit_u128<E: de::Error>(self, val: u128) -> Result<RuntimeVal, E> {
+        Ok(int_or_bigint(val))
+    }
+
+    fn visit_f64<E: de::Error>(self, val: f64) -> Result<RuntimeVal, E> {
+        Ok(RuntimeVal::Float(val))
+    }
+
+    fn visit_str<E: de::Error>(self, val: &str) -> Result<RuntimeVal, E> {
+        Ok(RuntimeVal::String(val.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, val: String) -> Result<RuntimeVal, E> {
+        Ok(RuntimeVal::String(val))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<RuntimeVal, E> {
+        Ok(option(None))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<RuntimeVal, E> {
+        Ok(option(None))
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<RuntimeVal, D::Error> {
+        Ok(option(Some(RuntimeVal::deserialize(deserializer)?)))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RuntimeVal, A::Error> {
+        let mut elements = vec![];
+        while let Some(element) = seq.next_element()? {
+            elements.push(element);
+        }
+
+        Ok(RuntimeVal::new_list(elements))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut entries: A) -> Result<RuntimeVal, A::Error> {
+        let mut map = Map::default();
+        while let Some((key, val)) = entries.next_entry::<RuntimeVal, RuntimeVal>()? {
+            let key = MapKey::try_from(key).map_err(de::Error::custom)?;
+            map.insert(key, val);
+        }
+
+        Ok(RuntimeVal::new_map(map))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, rc::Rc};
+
+    use serde::Deserialize;
+
+    use crate::{
+        engine::{Engine, EngineError},
+        runtime::native::NativeFunc,
+    };
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Shape {
+        Circle(f64),
+        Rect { width: f64, height: f64 },
+        Empty,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        name: String,
+        retries: u8,
+        ratio: Option<f64>,
+        tags: Vec<String>,
+        limits: HashMap<String, i64>,
+        shapes: Vec<Shape>,
+    }
+
+    fn config() -> Config {
+        Config {
+            name: "app".to_string(),
+            retries: 3,
+            ratio: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+            limits: HashMap::from([("max".to_string(), 10)]),
+            shapes: vec![
+                Shape::Circle(1.5),
+                Shape::Rect {
+                    width: 2.0,
+                    height: 3.0,
+                },
+                Shape::Empty,
+            ],
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let val = to_value(&config()).expect("Failed to serialize");
+        assert_eq!(
+            val.to_string(),
+            "{name: app, retries: 3, ratio: Option::None, tags: [a, b], limits: {max: 10}, \
+             shapes: [{Circle: 1.5}, {Rect: {width: 2, height: 3}}, Empty]}"
+        );
+        assert_eq!(from_value::<Config>(val), Ok(config()));
+
+        assert_eq!(
+            to_value(&u64::MAX).map(|val| val.to_string()),
+            Ok(u64::MAX.to_string())
+        );
+        assert_eq!(
+            from_value::<u64>(to_value(&u64::MAX).unwrap()),
+            Ok(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn script_values() {
+        let engine = Engine::new();
+        engine
+            .set_global_serde("config", &config())
+            .expect("Failed to set global");
+
+        let result = engine
+            .eval(
+                "
+        enum Shape { Circle(float) }
+        config[\"retries\"] = config[\"retries\"] + 1;
+        config[\"shapes\"] = [Shape::Circle(2.0)];
+        config
+        ",
+            )
+            .expect("Failed to evaluate");
+        let config: Config = from_value(result).expect("Failed to deserialize");
+        assert_eq!(config.retries, 4);
+        assert_eq!(config.shapes, vec![Shape::Circle(2.0)]);
+
+        let config: Config = engine
+            .get_global_as("config")
+            .expect("Failed to get global");
+        assert_eq!(config.retries, 4);
+
+        let result = engine
+            .eval("[Some(1), None, Err(\"failed\")]")
+            .expect("Failed to evaluate");
+        assert_eq!(
+            from_value::<(Option<i64>, Option<i64>, Result<i64, String>)>(result),
+            Ok((Some(1), None, Err("failed".to_string())))
+        );
+    }
+
+    #[test]
+    fn functions_are_not_data() {
+        let native =
+            RuntimeVal::NativeFunc(Rc::new(NativeFunc::new("f", 0, |_| Ok(RuntimeVal::Int(1)))));
+        let list = RuntimeVal::new_list(vec![native]);
+        let error = || RuntimeError::InvalidConversion("cannot convert a func value".to_string());
+
+        assert_eq!(to_value(&list), Err(error()));
+        assert_eq!(from_value::<Vec<i64>>(list), Err(error()));
+        assert!(matches!(
+            from_value::<Config>(RuntimeVal::Int(1)),
+            Err(RuntimeError::InvalidConversion(_))
+        ));
+
+        let engine = Engine::new();
+        engine.eval("let n: int = 1;").expect("Failed to evaluate");
+        assert!(matches!(
+            engine.get_global_as::<String>("n"),
+            Err(EngineError::Runtime(RuntimeError::InvalidConversion(_)))
+        ));
+        assert!(matches!(
+            engine.get_global("missing"),
+            Err(EngineError::Runtime(RuntimeError::UndefinedVariable(_)))
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+use super::{
+    convert::{FromRuntimeVal, IntoRuntimeVal},
+    environment::Environment,
+    interpreter::Interpreter,
+    values::{RuntimeError, RuntimeVal},
+};
+
+/// Register the string functions, indexes and lengths count characters, not
+/// bytes, the same way as `len`.
+pub fn register(env: &mut Environment) {
+    env.register_fn("upper", |text: String| Ok(text.to_uppercase()));
+    env.register_fn("lower", |text: String| Ok(text.to_lowercase()));
+    env.register_fn("trim", |text: String| Ok(text.trim().to_string()));
+
+    env.register_builtin("split", 2, |interpreter, args| {
+        let text = String::from_runtime_val(&args[0])?;
+        let separator = String::from_runtime_val(&args[1])?;
+
+        // An empty separator gives the characters instead of empty strings
+        // around each of them
+        let parts = if separator.is_empty() {
+            chars(&text)
+        } else {
+            text.split(&separator).map(str::to_string).collect()
+        };
+
+        string_list(interpreter, parts)
+    });
+    env.register_fn("join", |parts: Vec<String>, separator: String| {
+        Ok(parts.join(&separator))
+    });
+    // The size of the result is checked before it is built, every match of a
+    // short `from` can add a long `to`
+    env.register_builtin("replace", 3, |interpreter, args| {
+        let text = String::from_runtime_val(&args[0])?;
+        let from = String::from_runtime_val(&args[1])?;
+        let to = String::from_runtime_val(&args[2])?;
+
+        let matches = text.matches(&from).count();
+        let bytes =
+            (text.len() - matches * from.len()).saturating_add(matches.saturating_mul(to.len()));
+        interpreter.check_string_budget(bytes)?;
+
+        Ok(RuntimeVal::String(text.replace(&from, &to)))
+    });
+    // The size is checked first, a huge count would otherwise allocate or
+    // overflow before the string limit is applied to the result
+    env.register_builtin("repeat", 2, |interpreter, args| {
+        let text = String::from_runtime_val(&args[0])?;
+        let count = i64::from_runtime_val(&args[1])?;
+        let cannot_repeat =
+            || RuntimeError::InvalidConversion(format!("cannot repeat a string {count} times"));
+
+        let count = usize::try_from(count).map_err(|_| cannot_repeat())?;
+        // Allocations are limited to `isize::MAX` bytes
+        let bytes = text
+            .len()
+            .checked_mul(count)
+            .filter(|bytes| isize::try_from(*bytes).is_ok())
+            .ok_or_else(cannot_repeat)?;
+        interpreter.check_string_budget(bytes)?;
+
+        Ok(RuntimeVal::String(text.repeat(count)))
+    });
+    env.register_builtin("chars", 1, |interpreter, args| {
+        let text = String::from_runtime_val(&args[0])?;
+        string_list(interpreter, chars(&text))
+    });
+
+    env.register_fn("contains", |text: String, needle: String| {
+        Ok(text.contains(&needle))
+    });
+    env.register_fn("starts_with", |text: String, prefix: String| {
+        Ok(text.starts_with(&prefix))
+    });
+    env.register_fn("ends_with", |text: String, suffix: String| {
+        Ok(text.ends_with(&suffix))
+    });
+    env.register_fn("index_of", |text: String, needle: String| {
+        Ok(text
+            .find(&needle)
+            .map(|byte| text[..byte].chars().count() as i64))
+    });
+    env.register_fn("substring", substring);
+
+    env.register_fn("parse_int", |text: String| {
+        text.parse::<i64>().map_err(|_| cannot_parse(&text, "int"))
+    });
+    env.register_fn("parse_float", |text: String| {
+        text.parse::<f64>()
+            .map_err(|_| cannot_parse(&text, "float"))
+    });
+}
+
+fn chars(text: &str) -> Vec<String> {
+    text.chars().map(String::from).collect()
+}
+
+/// List of newly created strings, counted against the string limit.
+fn string_list(interpreter: &Interpreter, parts: Vec<String>) -> Result<RuntimeVal, RuntimeError> {
+    interpreter.track_string_bytes(parts.iter().map(String::len).sum())?;
+
+    Ok(parts.into_runtime_val())
+}
+
+pub fn cannot_parse(text: &str, typing: &str) -> RuntimeError {
+    RuntimeError::InvalidConversion(format!("cannot parse \"{text}\" as {typing}"))
+}
+
+/// Characters from `start` included to `end` excluded.
+fn substring(text: String, start: i64, end: i64) -> Result<String, RuntimeError> {
+    let len = text.chars().count();
+    for index in [start, end] {
+        if index < 0 || index as usize > len {
+            return Err(RuntimeError::IndexOutOfBounds { index, len });
+        }
+    }
+    if start > end {
+        return Err(RuntimeError::IndexOutOfBounds { index: start, len });
+    }
+
+    Ok(text
+        .chars()
+        .skip(start as usize)
+        .take((end - start) as usize)
+        .collect())
+}
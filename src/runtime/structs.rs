@@ -0,0 +1,102 @@
+use crate::ast::types::Type;
+
+use super::values::{RuntimeError, RuntimeVal};
+
+pub const ERROR_STRUCT: &str = "Error";
+
+/// Declared shape of a struct, fields are kept in declaration order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructDef {
+    pub name: String,
+    pub fields: Vec<(String, Type)>,
+}
+
+impl StructDef {
+    /// Struct of the runtime errors caught by scripts, declared in the global
+    /// environment. Its name is reserved, no other struct can be named `Error`
+    /// in any scope.
+    pub fn error() -> Self {
+        StructDef {
+            name: ERROR_STRUCT.to_string(),
+            fields: vec![
+                ("kind".to_string(), Type::String),
+                ("message".to_string(), Type::String),
+            ],
+        }
+    }
+
+    fn field_type(&self, field: &str) -> Result<&Type, RuntimeError> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, typing)| typing)
+            .ok_or_else(|| RuntimeError::UndefinedField(field.to_string()))
+    }
+
+    /// Build an instance, every declared field must be given exactly once
+    /// with a value matching its declared type.
+    pub fn instantiate(
+        &self,
+        mut values: Vec<(String, RuntimeVal)>,
+    ) -> Result<StructInstance, RuntimeError> {
+        for (field, val) in &values {
+            if !val.matches_type(self.field_type(field)?) {
+                return Err(RuntimeError::InvalidType);
+            }
+        }
+
+        let mut fields = vec![];
+        for (name, _) in &self.fields {
+            let position = values
+                .iter()
+                .position(|(field, _)| field == name)
+                .ok_or_else(|| RuntimeError::MissingField(name.clone()))?;
+            fields.push(values.swap_remove(position));
+        }
+
+        if let Some((field, _)) = values.first() {
+            return Err(RuntimeError::FieldRedeclaration(field.clone()));
+        }
+
+        Ok(StructInstance {
+            def: self.clone(),
+            fields,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructInstance {
+    pub def: StructDef,
+    fields: Vec<(String, RuntimeVal)>,
+}
+
+impl StructInstance {
+    pub fn get(&self, field: &str) -> Result<RuntimeVal, RuntimeError> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, val)| val.clone())
+            .ok_or_else(|| RuntimeError::UndefinedField(field.to_string()))
+    }
+
+    /// Assign a field, using the same type rules as the construction.
+    pub fn set(&mut self, field: &str, val: RuntimeVal) -> Result<(), RuntimeError> {
+        if !val.matches_type(self.def.field_type(field)?) {
+            return Err(RuntimeError::InvalidType);
+        }
+
+        for (name, current) in self.fields.iter_mut() {
+            if name == field {
+                *current = val;
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(String, RuntimeVal)> {
+        self.fields.iter()
+    }
+}
@@ -1,16 +1,35 @@
-use std::fmt::Display;
+use std::{cell::RefCell, collections::HashSet, fmt::Display, rc::Rc, time::Duration};
 
-use crate::ast::stmt::Stmt;
+use crate::{
+    ast::{atom::Atom, stmt::Stmt, types::Type},
+    bigint::BigInt,
+};
+
+use super::{environment::Env, map::Map, native::NativeFunc, structs::StructInstance};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum RuntimeVal {
     Int(i64),
+    BigInt(BigInt),
     Float(f64),
     Bool(bool),
     String(String),
-    Func(String, Vec<String>, Stmt),
+    // Lists are shared, copies of the value refer to the same elements
+    List(Rc<RefCell<Vec<RuntimeVal>>>),
+    // Maps are shared the same way as lists
+    Map(Rc<RefCell<Map>>),
+    // Struct instances are shared as well
+    Struct(Rc<RefCell<StructInstance>>),
+    // enum name, variant name, fields
+    Enum(String, String, Vec<RuntimeVal>),
+    // name, parameters, body (shared so calls don't copy the AST)
+    Func(String, Vec<String>, Rc<Stmt>),
+    // Function provided by the host
+    NativeFunc(Rc<NativeFunc>),
     Block(Box<RuntimeVal>),
     Return(Box<RuntimeVal>),
+    // function, arguments, declaration scope of a call in tail position
+    TailCall(Box<RuntimeVal>, Vec<RuntimeVal>, Env),
     Undefined,
 }
 
@@ -18,23 +37,397 @@ impl Display for RuntimeVal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             RuntimeVal::Int(val) => write!(f, "{val}"),
+            RuntimeVal::BigInt(val) => write!(f, "{val}"),
             RuntimeVal::Float(val) => write!(f, "{val}"),
             RuntimeVal::Bool(val) => write!(f, "{val}"),
             RuntimeVal::String(val) => write!(f, "{val}"),
+            RuntimeVal::List(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                write!(f, "]")
+            }
+            RuntimeVal::Map(map) => {
+                write!(f, "{{")?;
+                for (i, (key, val)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {val}", RuntimeVal::from(key))?;
+                }
+                write!(f, "}}")
+            }
+            RuntimeVal::Struct(instance) => {
+                let instance = instance.borrow();
+                write!(f, "{} {{ ", instance.def.name)?;
+                for (i, (field, val)) in instance.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{field}: {val}")?;
+                }
+                write!(f, " }}")
+            }
+            RuntimeVal::Enum(name, variant, fields) => {
+                write!(f, "{name}::{variant}")?;
+                if !fields.is_empty() {
+                    write!(f, "(")?;
+                    for (i, field) in fields.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{field}")?;
+                    }
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
             _ => write!(f, "{:#?}", self),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl RuntimeVal {
+    pub fn new_list(elements: Vec<RuntimeVal>) -> Self {
+        RuntimeVal::List(Rc::new(RefCell::new(elements)))
+    }
+
+    pub fn new_map(map: Map) -> Self {
+        RuntimeVal::Map(Rc::new(RefCell::new(map)))
+    }
+
+    /// Name of the value's type as written in declarations.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            RuntimeVal::Int(_) => "int",
+            RuntimeVal::BigInt(_) => "bigint",
+            RuntimeVal::Float(_) => "float",
+            RuntimeVal::Bool(_) => "bool",
+            RuntimeVal::String(_) => "string",
+            RuntimeVal::List(_) => "list",
+            RuntimeVal::Map(_) => "map",
+            RuntimeVal::Struct(_) => "struct",
+            RuntimeVal::Enum(..) => "enum",
+            RuntimeVal::Func(..) | RuntimeVal::NativeFunc(_) | RuntimeVal::TailCall(..) => "func",
+            RuntimeVal::Block(_) | RuntimeVal::Return(_) | RuntimeVal::Undefined => "undefined",
+        }
+    }
+
+    /// Check the value against a declared type, values without a declarable
+    /// type (functions) are accepted as before.
+    pub fn matches_type(&self, typing: &Type) -> bool {
+        match (self, typing) {
+            (_, Type::Any)
+            | (RuntimeVal::Int(_), Type::Int)
+            | (RuntimeVal::BigInt(_), Type::BigInt)
+            | (RuntimeVal::Float(_), Type::Float)
+            | (RuntimeVal::String(_), Type::String)
+            | (RuntimeVal::Bool(_), Type::Bool) => true,
+            (RuntimeVal::List(elements), Type::List(element_type)) => elements
+                .borrow()
+                .iter()
+                .all(|element| element.matches_type(element_type)),
+            (RuntimeVal::Map(map), Type::Map(key_type, value_type)) => {
+                map.borrow().iter().all(|(key, val)| {
+                    RuntimeVal::from(key).matches_type(key_type) && val.matches_type(value_type)
+                })
+            }
+            (RuntimeVal::Struct(instance), Type::Named(name)) => {
+                instance.borrow().def.name == *name
+            }
+            (RuntimeVal::Enum(enum_name, ..), Type::Named(name)) => enum_name == name,
+            (
+                RuntimeVal::Int(_)
+                | RuntimeVal::BigInt(_)
+                | RuntimeVal::Float(_)
+                | RuntimeVal::String(_)
+                | RuntimeVal::Bool(_)
+                | RuntimeVal::List(_)
+                | RuntimeVal::Map(_)
+                | RuntimeVal::Struct(_)
+                | RuntimeVal::Enum(..),
+                _,
+            ) => false,
+            _ => true,
+        }
+    }
+
+    /// Whether the value is `container` or holds it, storing it in
+    /// `container` would then create a cycle that `Display` and
+    /// `matches_type` could never finish walking.
+    pub fn refers_to(&self, container: &RuntimeVal) -> bool {
+        match container.shared_ptr() {
+            Some(ptr) => self.reaches(ptr, &mut HashSet::new()),
+            None => false,
+        }
+    }
+
+    fn shared_ptr(&self) -> Option<*const ()> {
+        match self {
+            RuntimeVal::List(elements) => Some(Rc::as_ptr(elements) as *const ()),
+            RuntimeVal::Map(map) => Some(Rc::as_ptr(map) as *const ()),
+            RuntimeVal::Struct(instance) => Some(Rc::as_ptr(instance) as *const ()),
+            _ => None,
+        }
+    }
+
+    // Values shared by several elements are only walked once
+    fn reaches(&self, ptr: *const (), visited: &mut HashSet<*const ()>) -> bool {
+        if let Some(current) = self.shared_ptr() {
+            if current == ptr {
+                return true;
+            }
+            if !visited.insert(current) {
+                return false;
+            }
+        }
+
+        match self {
+            RuntimeVal::List(elements) => elements
+                .borrow()
+                .iter()
+                .any(|element| element.reaches(ptr, visited)),
+            RuntimeVal::Map(map) => map
+                .borrow()
+                .iter()
+                .any(|(_, val)| val.reaches(ptr, visited)),
+            RuntimeVal::Struct(instance) => instance
+                .borrow()
+                .iter()
+                .any(|(_, val)| val.reaches(ptr, visited)),
+            RuntimeVal::Enum(_, _, fields) => {
+                fields.iter().any(|field| field.reaches(ptr, visited))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl From<&Atom> for RuntimeVal {
+    fn from(atom: &Atom) -> Self {
+        match atom {
+            Atom::Integer(num) => RuntimeVal::Int(*num),
+            Atom::BigInt(num) => RuntimeVal::BigInt(num.clone()),
+            Atom::Float(num) => RuntimeVal::Float(*num),
+            Atom::String(string) => RuntimeVal::String(string.clone()),
+            Atom::Bool(val) => RuntimeVal::Bool(*val),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub enum RuntimeError {
     ConstantReassignment(String),
     VarRedeclaration(String),
     FuncRedeclaration(String),
     InvalidOperandType,
     DivisionByZero,
+    IntegerOverflow {
+        op: String,
+    },
+    // Infinite or NaN float result under the strict numeric policy
+    NonFiniteResult {
+        op: String,
+    },
+    // Shift amount outside of 0..64
+    ShiftOutOfRange(i64),
     UndefinedVariable(String),
     UndefinedFunction(String),
+    UndefinedMethod(String),
     InvalidFuncCallParametersCount(String),
     InvalidType,
+    // A host function got a value it can't convert, e.g. `string` for an `i64`
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+    // A value can't be converted, e.g. a function with serde or "a" with
+    // `parse_int`
+    InvalidConversion(String),
+    // Pattern given to a regex function doesn't compile, with the message of
+    // the regex crate
+    InvalidRegex(String),
+    // Malformed text given to `json_parse`, positions are 1-based
+    InvalidJson {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    StackOverflow {
+        depth: usize,
+        function: String,
+    },
+    IndexOutOfBounds {
+        index: i64,
+        len: usize,
+    },
+    // List, map or struct stored inside itself
+    CyclicValue(String),
+    UndefinedKey(String),
+    InvalidMapKey(String),
+    UndefinedStruct(String),
+    StructRedeclaration(String),
+    UndefinedField(String),
+    MissingField(String),
+    FieldRedeclaration(String),
+    UndefinedEnum(String),
+    EnumRedeclaration(String),
+    UndefinedVariant(String),
+    // Value matched by none of the arms of a `match`
+    NoMatch(String),
+    // Value of a function call without `return` used in an expression
+    MissingReturnValue(String),
+    // `None` or `Err` returned early by `?`, it becomes the return value of
+    // the enclosing function call
+    Propagated(RuntimeVal),
+    // Value given to `throw` and not caught
+    Thrown(RuntimeVal),
+    StepLimitExceeded(u64),
+    TimeLimitExceeded(Duration),
+    StringLimitExceeded(usize),
+}
+
+impl RuntimeError {
+    /// Name of the error as exposed to scripts in the `kind` field of a
+    /// caught error.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuntimeError::ConstantReassignment(_) => "ConstantReassignment",
+            RuntimeError::VarRedeclaration(_) => "VarRedeclaration",
+            RuntimeError::FuncRedeclaration(_) => "FuncRedeclaration",
+            RuntimeError::InvalidOperandType => "InvalidOperandType",
+            RuntimeError::DivisionByZero => "DivisionByZero",
+            RuntimeError::IntegerOverflow { .. } => "IntegerOverflow",
+            RuntimeError::NonFiniteResult { .. } => "NonFiniteResult",
+            RuntimeError::ShiftOutOfRange(_) => "ShiftOutOfRange",
+            RuntimeError::UndefinedVariable(_) => "UndefinedVariable",
+            RuntimeError::UndefinedFunction(_) => "UndefinedFunction",
+            RuntimeError::UndefinedMethod(_) => "UndefinedMethod",
+            RuntimeError::InvalidFuncCallParametersCount(_) => "InvalidFuncCallParametersCount",
+            RuntimeError::InvalidType => "InvalidType",
+            RuntimeError::TypeMismatch { .. } => "TypeMismatch",
+            RuntimeError::InvalidConversion(_) => "InvalidConversion",
+            RuntimeError::InvalidRegex(_) => "InvalidRegex",
+            RuntimeError::InvalidJson { .. } => "InvalidJson",
+            RuntimeError::StackOverflow { .. } => "StackOverflow",
+            RuntimeError::IndexOutOfBounds { .. } => "IndexOutOfBounds",
+            RuntimeError::CyclicValue(_) => "CyclicValue",
+            RuntimeError::UndefinedKey(_) => "UndefinedKey",
+            RuntimeError::InvalidMapKey(_) => "InvalidMapKey",
+            RuntimeError::UndefinedStruct(_) => "UndefinedStruct",
+            RuntimeError::StructRedeclaration(_) => "StructRedeclaration",
+            RuntimeError::UndefinedField(_) => "UndefinedField",
+            RuntimeError::MissingField(_) => "MissingField",
+            RuntimeError::FieldRedeclaration(_) => "FieldRedeclaration",
+            RuntimeError::UndefinedEnum(_) => "UndefinedEnum",
+            RuntimeError::EnumRedeclaration(_) => "EnumRedeclaration",
+            RuntimeError::UndefinedVariant(_) => "UndefinedVariant",
+            RuntimeError::NoMatch(_) => "NoMatch",
+            RuntimeError::MissingReturnValue(_) => "MissingReturnValue",
+            RuntimeError::Propagated(_) => "Propagated",
+            RuntimeError::Thrown(_) => "Thrown",
+            RuntimeError::StepLimitExceeded(_) => "StepLimitExceeded",
+            RuntimeError::TimeLimitExceeded(_) => "TimeLimitExceeded",
+            RuntimeError::StringLimitExceeded(_) => "StringLimitExceeded",
+        }
+    }
+
+    /// Whether a script can handle the error with `try`/`catch`. Execution
+    /// limits can't be caught so a script can't keep running past them.
+    pub fn is_catchable(&self) -> bool {
+        !matches!(
+            self,
+            RuntimeError::StackOverflow { .. }
+                | RuntimeError::StepLimitExceeded(_)
+                | RuntimeError::TimeLimitExceeded(_)
+                | RuntimeError::StringLimitExceeded(_)
+                | RuntimeError::Propagated(_)
+        )
+    }
 }
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeError::ConstantReassignment(name) => {
+                write!(f, "cannot reassign constant `{name}`")
+            }
+            RuntimeError::VarRedeclaration(name) => {
+                write!(f, "variable `{name}` is already declared")
+            }
+            RuntimeError::FuncRedeclaration(name) => {
+                write!(f, "function `{name}` is already declared")
+            }
+            RuntimeError::InvalidOperandType => write!(f, "invalid operand type"),
+            RuntimeError::DivisionByZero => write!(f, "division by zero"),
+            RuntimeError::IntegerOverflow { op } => write!(f, "integer overflow in `{op}`"),
+            RuntimeError::NonFiniteResult { op } => {
+                write!(f, "non finite float result in `{op}`")
+            }
+            RuntimeError::ShiftOutOfRange(amount) => {
+                write!(f, "shift amount {amount} out of range 0..64")
+            }
+            RuntimeError::UndefinedVariable(name) => write!(f, "undefined variable `{name}`"),
+            RuntimeError::UndefinedFunction(name) => write!(f, "undefined function `{name}`"),
+            RuntimeError::UndefinedMethod(name) => write!(f, "undefined method `{name}`"),
+            RuntimeError::InvalidFuncCallParametersCount(name) => {
+                write!(f, "invalid number of arguments for `{name}`")
+            }
+            RuntimeError::InvalidType => write!(f, "invalid type"),
+            RuntimeError::TypeMismatch { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            RuntimeError::InvalidConversion(message) => write!(f, "{message}"),
+            RuntimeError::InvalidRegex(message) => write!(f, "{message}"),
+            RuntimeError::InvalidJson {
+                message,
+                line,
+                column,
+            } => write!(f, "{message} at line {line} and column {column}"),
+            RuntimeError::StackOverflow { depth, function } => {
+                write!(f, "stack overflow at depth {depth} calling `{function}`")
+            }
+            RuntimeError::IndexOutOfBounds { index, len } => {
+                write!(f, "index {index} out of bounds for length {len}")
+            }
+            RuntimeError::CyclicValue(typing) => {
+                write!(f, "cannot store a {typing} inside itself")
+            }
+            RuntimeError::UndefinedKey(key) => write!(f, "undefined key `{key}`"),
+            RuntimeError::InvalidMapKey(typing) => write!(f, "invalid map key type `{typing}`"),
+            RuntimeError::UndefinedStruct(name) => write!(f, "undefined struct `{name}`"),
+            RuntimeError::StructRedeclaration(name) => {
+                write!(f, "struct `{name}` is already declared")
+            }
+            RuntimeError::UndefinedField(name) => write!(f, "undefined field `{name}`"),
+            RuntimeError::MissingField(name) => write!(f, "missing field `{name}`"),
+            RuntimeError::FieldRedeclaration(name) => {
+                write!(f, "field `{name}` is given more than once")
+            }
+            RuntimeError::UndefinedEnum(name) => write!(f, "undefined enum `{name}`"),
+            RuntimeError::EnumRedeclaration(name) => {
+                write!(f, "enum `{name}` is already declared")
+            }
+            RuntimeError::UndefinedVariant(name) => write!(f, "undefined variant `{name}`"),
+            RuntimeError::NoMatch(val) => write!(f, "no match arm for value {val}"),
+            RuntimeError::MissingReturnValue(name) => {
+                write!(f, "function `{name}` does not return a value")
+            }
+            RuntimeError::Propagated(val) => write!(f, "unhandled {val}"),
+            RuntimeError::Thrown(val) => write!(f, "uncaught exception: {val}"),
+            RuntimeError::StepLimitExceeded(steps) => write!(f, "step limit of {steps} exceeded"),
+            RuntimeError::TimeLimitExceeded(duration) => {
+                write!(f, "time limit of {duration:?} exceeded")
+            }
+            RuntimeError::StringLimitExceeded(bytes) => {
+                write!(f, "string limit of {bytes} bytes exceeded")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
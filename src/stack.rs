@@ -0,0 +1,14 @@
+//! Stack growth for the recursive passes over a program: the parser, the
+//! checker, the optimizer and the interpreter all recurse once per level of
+//! nesting, with frames of several KiB in debug builds.
+
+// Once less than `RED_ZONE` bytes of stack are left, the pass goes on in a new
+// segment allocated on the heap, so the depth of a program is bounded by the
+// parser's nesting limit and `max_call_depth` rather than by the stack of the
+// host thread
+const RED_ZONE: usize = 256 * 1024;
+const SEGMENT_SIZE: usize = 2 * 1024 * 1024;
+
+pub(crate) fn ensure_sufficient_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(RED_ZONE, SEGMENT_SIZE, f)
+}